@@ -0,0 +1,170 @@
+// Generated by `cargo run -p sgbf-api --features schema --bin schema`, do not edit.
+
+export interface Address {
+    street?: string | null;
+    postcode?: string | null;
+    city?: string | null;
+}
+
+export interface Addresses {
+    phone?: string | null;
+    fax?: string | null;
+    email?: string | null;
+    mobile?: string | null;
+}
+
+export interface Aircraft {
+    registrationNumber: string;
+    model: string;
+    competitionNumber?: string | null;
+}
+
+export type AircraftClass = "SingleSeater" | "TwoSeater" | "MotorGlider" | "Tug" | "Unknown";
+
+export interface Day {
+    entries: RosterEntry[];
+    action: EditAction;
+    id?: number | null;
+    participantType: ParticipantType;
+    format: string;
+    remarks?: string | null;
+    entryType?: RosterEntryType | null;
+    reservations?: Reservation[] | null;
+}
+
+export interface DayOverview {
+    date: string;
+    registeredPilots: Stats;
+    entries: PersonEntry[];
+    note?: string | null;
+    reservations?: Reservation[] | null;
+}
+
+/** A duty slot of a flight instructor, tow pilot or winch operator on a given day. */
+export interface Duty {
+    action: EditAction;
+    id?: number | null;
+    participantType: ParticipantType;
+    format: string;
+    timeFrame?: TimeFrame | null;
+    remarks?: string | null;
+}
+
+export type EditAction = "edit" | "add";
+
+export type EntryType = "FlightInstructor" | "TowingPilot" | "WinchOperator" | string;
+
+export interface FeedResponse {
+    secret: string;
+    /** Relative to the api, for calendar apps to subscribe to. */
+    path: string;
+}
+
+/** An aircraft of the club's fleet, as offered on the reservation page. */
+export interface FleetAircraft {
+    class: AircraftClass;
+    seats?: number | null;
+    /** Disabled entries are listed upstream but can't be reserved. */
+    reservable: boolean;
+    registrationNumber: string;
+    model: string;
+    competitionNumber?: string | null;
+}
+
+export interface LoginRequest {
+    username: string;
+    password: string;
+}
+
+export interface LoginResponse {
+    token: string;
+}
+
+export interface Member {
+    /** Upstream's member id if the directory links one, otherwise derived from the name. */
+    id: string;
+    name: string;
+    address?: Address | null;
+    private: Addresses;
+    office: Addresses;
+    role?: string | null;
+}
+
+export interface MyEntries {
+    personId: string;
+    /** Upcoming days the user is entered on, with only their own entries. */
+    days: DayOverview[];
+    reservations: Reservation[];
+}
+
+export interface NotificationSettings {
+    enabled: boolean;
+    flightInstructors: boolean;
+    potentialFlightInstructors: boolean;
+    flightInstructorRequests: boolean;
+    towPilots: boolean;
+    potentialTowPilots: boolean;
+    towPilotRequests: boolean;
+}
+
+export type ParticipantType = "participant_sf" | "participant_fi" | "participant_s" | "participant_w";
+
+/** A span of time from `from` up to, but not including, `to`. Periods where `to` is not after `from` are empty: they cover no time and overlap nothing. */
+export interface Period {
+    from: string;
+    to: string;
+}
+
+export interface PersonEntry {
+    timeFrame: TimeFrame;
+    name: string;
+    entryType: EntryType;
+    note1?: string | null;
+    note2?: string | null;
+}
+
+export interface Reservation {
+    id: number;
+    period: Period;
+    plane: Aircraft;
+    reservedBy: string;
+    /** Start of the day the reservation was made, upstream doesn't show the time. */
+    createdAt: string;
+    comments: string[];
+}
+
+/** A reservation to be created or modified upstream. */
+export interface ReservationRequest {
+    plane: Aircraft;
+    period: Period;
+    comment?: string | null;
+}
+
+export interface RosterEntry {
+    name: string;
+    message: string;
+    entryType: RosterEntryType;
+}
+
+export type RosterEntryType = "Definite" | "Tentative" | "Unavailable";
+
+export interface Stats {
+    definitive: number;
+    tentative: number;
+}
+
+/** Wall-clock times in `crate::model::time::TIMEZONE` from `start` up to, but not including, `end`. See `crate::model::Period::on` for the actual period on a given day. */
+export interface TimeFrame {
+    start: string;
+    end: string;
+}
+
+export interface User {
+    id: string;
+    name: string;
+    settings: UserSettings;
+}
+
+export interface UserSettings {
+    notifications: NotificationSettings;
+}
@@ -1,7 +1,18 @@
 import {defineStore} from 'pinia';
-import type {Start} from '@/model';
 import {ref} from "vue";
 
+// start lists are only kept in the browser, the backend has no counterpart
+export interface Start {
+    from: string,
+    to: string,
+    pic: string,
+    copilot: string,
+    isPax: boolean,
+    plane: string,
+    updated: Date,
+    readonly id?: number,
+}
+
 export const startListStore = defineStore('startListStore', () => {
 
     // start list cache
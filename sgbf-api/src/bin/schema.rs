@@ -0,0 +1,7 @@
+//! Writes the frontend's model definitions, see `sgbf_api::schema`.
+
+fn main() -> anyhow::Result<()> {
+    sgbf_api::schema::write()?;
+    println!("wrote {} and {}", sgbf_api::schema::typescript_path().display(), sgbf_api::schema::json_schema_path().display());
+    Ok(())
+}
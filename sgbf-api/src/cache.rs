@@ -1,21 +1,21 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use anyhow::{bail, Context};
+use anyhow::Context;
 use axum::headers::authorization::Credentials;
 use chrono::NaiveDate;
-use firestore::FirestoreDb;
-use onesignal_rust_api::apis;
-use onesignal_rust_api::apis::configuration::Configuration;
-use onesignal_rust_api::apis::default_api::CreateNotificationError;
-use onesignal_rust_api::apis::Error;
-use onesignal_rust_api::models::{Notification, StringMap};
 use tokio::select;
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::timeout;
 use tracing::{debug, error, event, info, instrument, Level, warn};
+use sgbf_client::diff::{CalendarDiff, DayChange, DayDiff, Snapshot};
+use sgbf_client::identity::{Directory, PersonId};
+use sgbf_client::source::RosterSource;
 use sgbf_client::model::{Day, DayOverview, RosterEntryType};
+use sgbf_client::model::query::{CalendarQuery, ReservationQuery};
+use sgbf_client::model::time::TIMEZONE;
 use crate::config::CacheConfig;
+use crate::onesignal::Notifier;
 
 const REGISTERED_PILOTS_THRESHOLD: u32 = 10;
 
@@ -24,6 +24,8 @@ pub struct Calendar {
     pub day_overviews: Vec<sgbf_client::model::DayOverview>,
     pub reservations: Vec<sgbf_client::model::Reservation>,
     pub members: Vec<sgbf_client::model::Member>,
+    pub fleet: Vec<sgbf_client::model::aircraft::FleetAircraft>,
+    pub people: Directory,
     pub days: HashMap<NaiveDate, (Instant, Day)>
 }
 
@@ -58,31 +60,38 @@ pub type CacheRef = Arc<Cache>;
 pub struct Cache {
     pub last_update: Arc<RwLock<chrono::DateTime<chrono::Utc>>>,
     pub inner: Arc<RwLock<Calendar>>,
-    db: FirestoreDb,
     credentials: (String, String),
+    name_overrides: Arc<HashMap<String, String>>,
+    prefetch_days: usize,
+    source: Arc<dyn RosterSource>,
     tx_handle: mpsc::Sender<()>,
     rx_handle: Arc<RwLock<mpsc::Receiver<()>>>,
-    notifications: Arc<Option<Configuration>>
+    notifier: Option<Arc<dyn Notifier>>,
 }
 
 impl Cache {
 
-    pub fn new(db: FirestoreDb, config: &CacheConfig, notifications: Option<Configuration>) -> Self {
+    pub fn new(config: &CacheConfig, source: Arc<dyn RosterSource>, notifier: Option<Arc<dyn Notifier>>) -> Self {
         let (tx, rx) = mpsc::channel(1);
         Self {
             last_update: Arc::new(RwLock::new(chrono::Utc::now())),
             inner: Arc::new(RwLock::new(Default::default())),
             credentials: (config.username.to_owned(), config.password.to_owned()),
-            db,
+            name_overrides: Arc::new(config.name_overrides.clone()),
+            prefetch_days: config.prefetch_days,
+            source,
             tx_handle: tx,
             rx_handle: Arc::new(RwLock::new(rx)),
-            notifications: Arc::new(notifications)
+            notifier,
         }
     }
 
     pub async fn mark_dirty(&self) {
         info!("explicitly updating cache");
-        self.tx_handle.send(()).await.unwrap();
+        // a full channel means an update is already pending
+        if let Err(mpsc::error::TrySendError::Closed(_)) = self.tx_handle.try_send(()) {
+            warn!("cache polling is not running");
+        }
     }
 
     #[instrument(skip(self))]
@@ -104,117 +113,215 @@ impl Cache {
     }
 
     async fn update(&self) -> anyhow::Result<()> {
-        let client = sgbf_client::Client::from_credentials(&self.credentials.0, &self.credentials.1).await.context("failed to create client")?;
         // update calendar
-        let reservations = client.get_reservations().await.context("failed to update reservations")?;
-        let members = client.get_members().await.context("failed to update members")?;
-        let calendar = client.get_calendar().await.context("failed to update calendar")?;
-        let mut inner = self.inner.write().await;
-        let old_calendar = inner.clone();
-        // todo: compare old calendar to new one, send notifications for changes
-        inner.day_overviews = calendar.clone();
-        inner.reservations = reservations.clone();
-        inner.members = members.clone();
-        let mut guard = self.last_update.write().await;
-        *guard = chrono::Utc::now();
+        let reservations = self.source.get_reservations(&ReservationQuery::default()).await.context("failed to update reservations")?;
+        let members = self.source.get_members().await.context("failed to update members")?;
+        let fleet = self.source.get_fleet().await.context("failed to update fleet")?;
+        let calendar = self.source.get_calendar(&CalendarQuery::default()).await.context("failed to update calendar")?;
+        let people = self.name_overrides.iter()
+            .fold(Directory::new(&members), |people, (name, id)| people.with_override(name, PersonId::new(id.as_str())));
+        // decide which days to refresh against the new overviews, without blocking readers
+        let old_calendar = self.inner.read().await.clone();
+        let mut next = Calendar {
+            day_overviews: calendar,
+            reservations,
+            members,
+            fleet,
+            people,
+            days: old_calendar.days.clone(),
+        };
         // only keep cached days in current period
-        inner.days.retain(|date, (_, _)| {
-            calendar.iter().any(|overview| overview.date == *date)
-        });
-        // check if any day caches are dirty or expired, update if necessary
-        let days = inner.days.clone();
-        for date in days.keys() {
-            if inner.is_dirty(*date) {
-                let day = client.get_day(*date).await.context("failed to update day cache")?;
-                inner.days.insert(*date, (Instant::now() + Duration::from_secs(600 * 3), day));
-                // todo: compare old day to new one, send notifications for changes
-            }
+        let upcoming = next.day_overviews.iter()
+            .map(|overview| overview.date)
+            .take(self.prefetch_days)
+            .collect::<Vec<_>>();
+        next.days.retain(|date, _| upcoming.contains(date));
+        let dirty = upcoming.into_iter()
+            .filter(|date| next.is_dirty(*date))
+            .collect::<Vec<_>>();
+        let batch = self.source.get_days(dirty).await;
+        for (date, error) in &batch.failures {
+            warn!(%date, %error, "failed to update day cache");
         }
-        let new_calendar = inner.clone();
-        self.compare_calendars(old_calendar, new_calendar).await?;
+        let expiry = Instant::now() + Duration::from_secs(600 * 3);
+        // days that failed keep their stale copy until the next update
+        next.days.extend(batch.days.into_iter().map(|(date, day)| (date, (expiry, day))));
+        *self.inner.write().await = next.clone();
+        *self.last_update.write().await = chrono::Utc::now();
+        self.compare_calendars(old_calendar, next).await?;
         Ok(())
     }
 
-    async fn compare_calendars(&self, mut old: Calendar, mut new: Calendar) -> anyhow::Result<()> {
+    async fn compare_calendars(&self, old: Calendar, new: Calendar) -> anyhow::Result<()> {
+        // nothing to compare against right after startup
         if old.day_overviews.is_empty() || new.day_overviews.is_empty() {
             return Ok(());
         }
-        // transform each day into vector of changes
-        if old.day_overviews.first().map(|overview| overview.date) != new.day_overviews.first().map(|overview| overview.date) {
-            // remove first of old days
-            old.day_overviews.remove(0);
-        }
-        if old.day_overviews.last().map(|overview| overview.date) != new.day_overviews.last().map(|overview| overview.date) {
-            // remove last of old days
-            new.day_overviews.pop();
+        let now = chrono::Utc::now().with_timezone(&TIMEZONE);
+        let diff = CalendarDiff::new(
+            Snapshot { days: &old.day_overviews, reservations: &old.reservations, taken_at: now },
+            Snapshot { days: &new.day_overviews, reservations: &new.reservations, taken_at: now },
+            &new.people,
+        );
+        for day in &diff.days {
+            for change in &day.changes {
+                match change {
+                    DayChange::CountChanged { old, new } if old.definitive < new.definitive
+                        && new.definitive == REGISTERED_PILOTS_THRESHOLD => {
+                        // todo: notification for interested pilots
+                        info!(
+                            entry.threshold = %REGISTERED_PILOTS_THRESHOLD,
+                            entry.date = %day.date,
+                            "pilot threshold reached"
+                        );
+                        self.send_notification(&format!("{} pilot threshold reached for {}", REGISTERED_PILOTS_THRESHOLD, day.date)).await?;
+                    }
+                    DayChange::EntryAdded(entry) => {
+                        // todo: notification for interested pilots
+                        info!(
+                            entry.name = %entry.name,
+                            entry.r#type = ?entry.entry_type,
+                            entry.date = %day.date,
+                            "new entry for type {:?}", entry.entry_type
+                        );
+                        self.send_notification(&format!("new entry {} for {} (type {:?})", entry.name, day.date.format("%a, %-d %B"), entry.entry_type)).await?;
+                    }
+                    change => debug!(date = %day.date, ?change, "day changed"),
+                }
+            }
         }
-
-        if old.day_overviews.len() != new.day_overviews.len() {
-            bail!("calendar length mismatch, cannot compare calendars");
+        for change in &diff.reservations {
+            debug!(?change, "reservation changed");
         }
-
-        // zip old and new days together
-        let mut overviews = old.day_overviews.into_iter().zip(new.day_overviews.into_iter());
-        for (old_overview, new_overview) in overviews {
-            if old_overview.date != new_overview.date {
-                bail!("calendar date mismatch, cannot compare calendars");
-            }
-            let relevant_pilots = new.days.get(&new_overview.date)
-                .map(|(_, day)| day.entries.iter()
-                    .map(|entry| entry.name.clone()).collect::<Vec<_>>())
-                .unwrap_or_default();
-            // change in registered pilots
-            if old_overview.registered_pilots.definitive < new_overview.registered_pilots.definitive
-                && new_overview.registered_pilots.definitive == REGISTERED_PILOTS_THRESHOLD {
-                // todo: notification for interested pilots
-                info!(
-                    entry.threshold = %REGISTERED_PILOTS_THRESHOLD,
-                    entry.date = %new_overview.date,
-                    "pilot threshold reached"
-                );
-                self.send_notification(&format!("{} pilot threshold reached for {}", REGISTERED_PILOTS_THRESHOLD, new_overview.date)).await?;
-            }
-
-            let old_entries = old_overview.entries;
-            let new_entries = new_overview.entries;
-            // get entries in new entries that are not in old entries
-            let new_entries = new_entries.into_iter().filter(|new_entry| {
-                !old_entries.iter().any(|old_entry| old_entry.name == new_entry.name)
-            }).collect::<Vec<_>>();
-            for new_entry in new_entries {
-                // todo: notification for interested pilots
-                info!(
-                    entry.name = %new_entry.name,
-                    entry.r#type = ?new_entry.entry_type,
-                    entry.date = %new_overview.date,
-                    "new entry for type {:?}", new_entry.entry_type
-                );
-                self.send_notification(&format!("new entry {} for {} (type {:?})", new_entry.name, new_overview.date.format("%a, %-d %B"), new_entry.entry_type)).await?;
+        for day in compare_days(&old, &new) {
+            for change in &day.changes {
+                match change {
+                    DayChange::SignedUp(entry) => {
+                        // todo: notification for interested pilots
+                        info!(
+                            entry.name = %entry.name,
+                            entry.r#type = ?entry.entry_type,
+                            entry.date = %day.date,
+                            "new sign-up"
+                        );
+                        self.send_notification(&format!("{} signed up for {} ({:?})", entry.name, day.date.format("%a, %-d %B"), entry.entry_type)).await?;
+                    }
+                    change => debug!(date = %day.date, ?change, "sign-ups changed"),
+                }
             }
         }
         Ok(())
     }
 
     async fn send_notification(&self, text: &str) -> anyhow::Result<()> {
-        if let Some(config) = self.notifications.as_ref() {
-            // todo: make app id configurable
-            let mut notification = Notification::new(String::from("597019c4-d476-4efa-9832-34791456301c"));
-            let mut contents = StringMap::new();
-            contents.en = Some(text.to_owned());
-            notification.contents = Some(Box::new(contents));
+        if let Some(notifier) = &self.notifier {
             // todo: configure users dynamically
-            notification.include_external_user_ids = Some(vec![self.credentials.0.clone()]);
-            let result = apis::default_api::create_notification(config, notification).await;
-            if let Err(Error::ResponseError(err)) = &result {
-                bail!("onesignal response error ({}), {:?}", err.status, err.entity)
-            }
-            result.context("failed to send notification")?;
+            notifier.notify(&self.credentials.0, text).await?;
         }
         Ok(())
     }
 
-    async fn compare_days(&self, old: Day, new: Day) {
+}
+
+/// Sign-up changes of the days cached in both calendars, days that weren't fetched again are the
+/// same copy and have none.
+fn compare_days(old: &Calendar, new: &Calendar) -> Vec<DayDiff> {
+    let mut diffs = new.days.iter()
+        .filter_map(|(date, (_, day))| old.days.get(date).map(|(_, old_day)| DayDiff::sign_ups(*date, old_day, day, &new.people)))
+        .filter(|diff| !diff.is_empty())
+        .collect::<Vec<_>>();
+    diffs.sort_by_key(|diff| diff.date);
+    diffs
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+    use axum::extract::{Query, State};
+    use axum::{Extension, Json};
+    use axum::async_trait;
+    use sgbf_client::model::RosterEntry;
+    use sgbf_client::source::{FakeRoster, FakeSource, FixtureSource};
+    use crate::routes::members::get_members;
+    use crate::store::Uid;
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct RecordingNotifier(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Notifier for RecordingNotifier {
+        async fn notify(&self, _user: &str, text: &str) -> anyhow::Result<()> {
+            self.0.lock().unwrap().push(text.to_owned());
+            Ok(())
+        }
+    }
+
+    async fn calendar(source: &FakeSource, dates: &[NaiveDate]) -> Calendar {
+        let batch = source.get_days(dates.to_vec()).await;
+        Calendar {
+            days: batch.days.into_iter().map(|(date, day)| (date, (Instant::now(), day))).collect(),
+            ..Calendar::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compare_days() {
+        let source = FakeSource::default();
+        let dates = [NaiveDate::from_ymd_opt(2023, 6, 4).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 5).unwrap()];
+        let old = calendar(&source, &dates).await;
+        assert!(compare_days(&old, &calendar(&source, &dates).await).is_empty());
 
+        let entry = RosterEntry {
+            name: "Hans Muster".to_string(),
+            message: String::new(),
+            entry_type: RosterEntryType::Definite,
+        };
+        let day = old.days[&dates[1]].1.clone();
+        source.update(|roster| {
+            roster.days.insert(dates[1], Day { entries: vec![entry.clone()], ..day });
+        });
+        let new = calendar(&source, &dates).await;
+        assert_eq!(compare_days(&old, &new), vec![DayDiff { date: dates[1], changes: vec![DayChange::SignedUp(entry)] }]);
     }
 
+    #[tokio::test]
+    async fn test_update_offline() {
+        let fixtures = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../sgbf-client/tests/data"));
+        let calendar = fixtures.get_calendar(&CalendarQuery::default()).await.unwrap();
+        let date = calendar[0].date;
+        let source = Arc::new(FakeSource::new(FakeRoster {
+            calendar,
+            members: fixtures.get_members().await.unwrap(),
+            ..Default::default()
+        }));
+        let notifier = Arc::new(RecordingNotifier::default());
+        let config = CacheConfig {
+            username: "cache".to_string(),
+            password: String::new(),
+            name_overrides: HashMap::new(),
+            prefetch_days: 1,
+            concurrency: 1,
+        };
+        let cache = Arc::new(Cache::new(&config, source.clone(), Some(notifier.clone() as Arc<dyn Notifier>)));
+        cache.update().await.unwrap();
+        assert!(notifier.0.lock().unwrap().is_empty());
+
+        let member = source.snapshot().members[0].name.clone();
+        let mut day = source.get_day(date).await.unwrap();
+        day.entries.push(RosterEntry { name: member.clone(), message: String::new(), entry_type: RosterEntryType::Definite });
+        source.update(|roster| {
+            roster.days.insert(date, day);
+        });
+        cache.update().await.unwrap();
+        let sent = notifier.0.lock().unwrap().clone();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].starts_with(&format!("{} signed up", member)));
+
+        let query = Query::try_from_uri(&format!("/members?q={}", member.replace(' ', "+")).parse().unwrap()).unwrap();
+        let Ok(Json(members)) = get_members(State(cache), query, Extension(Uid("test".to_string()))).await else {
+            panic!("members should be served from the cache");
+        };
+        assert!(members.iter().any(|found| found.name == member));
+    }
 }
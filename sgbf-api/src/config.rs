@@ -1,5 +1,8 @@
+use std::collections::HashMap;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use serde::Deserialize;
+use sgbf_client::limiter::{RateLimitConfig, RetryPolicy};
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Server {
@@ -16,6 +19,42 @@ pub struct Firebase {
 pub struct CacheConfig {
     pub username: String,
     pub password: String,
+    /// Names that can't be matched automatically, mapped to a member id.
+    #[serde(default)]
+    pub name_overrides: HashMap<String, String>,
+    /// How many upcoming days are kept in the cache.
+    #[serde(default = "default_prefetch_days")]
+    pub prefetch_days: usize,
+    /// How many days are fetched from upstream at once.
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+}
+
+fn default_prefetch_days() -> usize {
+    14
+}
+
+fn default_concurrency() -> usize {
+    4
+}
+
+/// Limits for all requests to upstream, shared by the cache and per-request clients.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Upstream {
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Serve captured pages from this directory instead of the live site, see
+    /// [`sgbf_client::source::FixtureSource`].
+    #[serde(default)]
+    pub fixtures: Option<PathBuf>,
+    /// Record every upstream exchange to this directory, see [`sgbf_client::transport`].
+    #[serde(default)]
+    pub record: Option<PathBuf>,
+    /// Replay upstream exchanges recorded to this directory instead of reaching the live site.
+    #[serde(default)]
+    pub replay: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -30,6 +69,8 @@ pub struct Config {
     pub cache: CacheConfig,
     pub firebase: Firebase,
     pub onesignal: OneSignal,
+    #[serde(default)]
+    pub upstream: Upstream,
     pub tracing: crate::tracing::TracingConfig
 }
 
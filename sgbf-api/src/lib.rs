@@ -3,6 +3,9 @@ pub mod state;
 pub mod server;
 pub mod routes;
 pub mod tracing;
+// also built for tests, so the staleness check runs without the feature
+#[cfg(any(test, feature = "schema"))]
+pub mod schema;
 mod cache;
 mod store;
 mod onesignal;
\ No newline at end of file
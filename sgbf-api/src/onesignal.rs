@@ -1,6 +1,40 @@
-use onesignal_rust_api::apis::configuration::{ApiKey, Configuration};
+use std::fmt::Debug;
+use std::sync::Arc;
+use anyhow::{bail, Context};
+use axum::async_trait;
+use onesignal_rust_api::apis;
+use onesignal_rust_api::apis::configuration::Configuration;
+use onesignal_rust_api::apis::Error;
+use onesignal_rust_api::models::{Notification, StringMap};
 use crate::config::OneSignal;
 
+/// Delivers messages about roster changes to users.
+#[async_trait]
+pub trait Notifier: Debug + Send + Sync {
+    async fn notify(&self, user: &str, text: &str) -> anyhow::Result<()>;
+}
+
+#[derive(Debug)]
+pub struct OneSignalNotifier(Configuration);
+
+#[async_trait]
+impl Notifier for OneSignalNotifier {
+    async fn notify(&self, user: &str, text: &str) -> anyhow::Result<()> {
+        // todo: make app id configurable
+        let mut notification = Notification::new(String::from("597019c4-d476-4efa-9832-34791456301c"));
+        let mut contents = StringMap::new();
+        contents.en = Some(text.to_owned());
+        notification.contents = Some(Box::new(contents));
+        notification.include_external_user_ids = Some(vec![user.to_owned()]);
+        let result = apis::default_api::create_notification(&self.0, notification).await;
+        if let Err(Error::ResponseError(err)) = &result {
+            bail!("onesignal response error ({}), {:?}", err.status, err.entity)
+        }
+        result.context("failed to send notification")?;
+        Ok(())
+    }
+}
+
 pub fn create_onesignal_configuration(config: &OneSignal) -> Option<Configuration> {
     let key = config.key.as_ref()?;
     let mut configuration = Configuration::new();
@@ -8,3 +42,8 @@ pub fn create_onesignal_configuration(config: &OneSignal) -> Option<Configuratio
     Some(configuration)
 }
 
+/// Sends notifications through OneSignal if a key is configured.
+pub fn create_notifier(config: &OneSignal) -> Option<Arc<dyn Notifier>> {
+    let configuration = create_onesignal_configuration(config)?;
+    Some(Arc::new(OneSignalNotifier(configuration)))
+}
@@ -1,5 +1,7 @@
 pub mod reservation;
 pub mod members;
+pub mod aircraft;
+pub mod feed;
 
 pub async fn status() -> &'static str {
     // todo: better status
@@ -0,0 +1,36 @@
+use axum::{extract, Json};
+use axum::extract::State;
+use axum_macros::debug_handler;
+use serde::Deserialize;
+use tracing::instrument;
+use sgbf_client::model::aircraft::FleetAircraft;
+use crate::server::ServerError;
+use crate::state::{Roster, SharedState};
+use crate::store::Uid;
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AircraftQuery {
+    reservable: Option<bool>,
+}
+
+#[debug_handler]
+#[instrument(skip(roster, state), fields(user = %_uid))]
+pub async fn get_aircraft(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Query(query): extract::Query<AircraftQuery>,
+    extract::Extension(Uid(_uid)): extract::Extension<Uid>
+) -> Result<Json<Vec<FleetAircraft>>, ServerError> {
+    let cache = state.inner.read().unwrap().cache.clone();
+    let cached = cache.inner.read().await.fleet.clone();
+    // the cache is empty until its first update
+    let fleet = match cached.is_empty() {
+        true => roster.get_fleet().await?,
+        false => cached,
+    };
+    let fleet = fleet.into_iter()
+        .filter(|aircraft| query.reservable.is_none_or(|reservable| aircraft.reservable == reservable))
+        .collect();
+    Ok(Json(fleet))
+}
@@ -0,0 +1,102 @@
+use anyhow::Context;
+use axum::extract::{FromRef, State};
+use axum::http::header::CONTENT_TYPE;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::{extract, Json};
+use axum_macros::debug_handler;
+use firestore::FirestoreDb;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+use sgbf_client::ics::{self, Event};
+use sgbf_client::model::RosterEntryType;
+use crate::cache::CacheRef;
+use crate::server::{ServerError, UnknownServerError};
+use crate::state::SharedState;
+use crate::store::{self, get_user, Uid};
+
+#[derive(Serialize)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FeedResponse {
+    secret: String,
+    /// Relative to the api, for calendar apps to subscribe to.
+    path: String,
+}
+
+/// Creates a feed url for the user, previous urls stop working.
+#[debug_handler]
+#[instrument(skip(state), fields(user = %uid))]
+pub async fn create_feed(
+    State(state): State<SharedState>,
+    extract::Extension(Uid(uid)): extract::Extension<Uid>
+) -> Result<Json<FeedResponse>, UnknownServerError> {
+    let db = FirestoreDb::from_ref(&state);
+    let secret = store::create_feed(&db, &uid).await?;
+    info!("feed created");
+    let path = format!("/feed/{}.ics", secret);
+    Ok(Json(FeedResponse { secret, path }))
+}
+
+#[debug_handler]
+#[instrument(skip(state), fields(user = %uid))]
+pub async fn revoke_feed(
+    State(state): State<SharedState>,
+    extract::Extension(Uid(uid)): extract::Extension<Uid>
+) -> Result<StatusCode, UnknownServerError> {
+    let db = FirestoreDb::from_ref(&state);
+    store::revoke_feeds(&db, &uid).await?;
+    info!("feeds revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedQuery {
+    /// Include every flying day and everyone's duties, not just the user's own entries.
+    #[serde(default)]
+    roster: bool,
+}
+
+/// The user's duties, sign-ups and reservations as iCalendar, served from the cache. The secret
+/// in the url replaces the bearer token, since calendar apps can't send one.
+#[instrument(skip(state, secret), fields(roster = %query.roster))]
+pub async fn get_feed(
+    State(state): State<SharedState>,
+    extract::Path(secret): extract::Path<String>,
+    extract::Query(query): extract::Query<FeedQuery>
+) -> Result<Response, ServerError> {
+    let db = FirestoreDb::from_ref(&state);
+    let secret = secret.trim_end_matches(".ics");
+    let Some(uid) = store::get_uid_for_feed(&db, secret).await? else {
+        return Err(ServerError::InvalidToken);
+    };
+    let user = get_user(&db, &uid).await?.context("failed to get user")?;
+    let cache = CacheRef::from_ref(&state);
+    let calendar = cache.inner.read().await;
+    let person_id = calendar.people.person_id(&user.name);
+    let is_me = |name: &str| calendar.people.person_id(name) == person_id;
+
+    let mut feed = ics::Calendar::new(format!("SGBF {}", user.name));
+    for day in &calendar.day_overviews {
+        let stats = &day.registered_pilots;
+        if query.roster && (stats.definitive + stats.tentative > 0 || !day.entries.is_empty()) {
+            feed.push(Event::flying_day(day));
+        }
+        feed.extend(day.entries.iter()
+            .filter(|entry| query.roster || is_me(&entry.name))
+            .map(|entry| Event::duty(day.date, entry, &calendar.people.person_id(&entry.name))));
+    }
+    // only days in the cache, upstream lists sign-ups per day
+    for (date, (_, day)) in calendar.days.iter().sorted_by_key(|(date, _)| **date) {
+        feed.extend(day.entries.iter()
+            .filter(|entry| entry.entry_type != RosterEntryType::Unavailable && is_me(&entry.name))
+            .map(|entry| Event::sign_up(*date, entry, &person_id)));
+    }
+    feed.extend(calendar.reservations.iter()
+        .filter(|reservation| is_me(&reservation.reserved_by))
+        .map(Event::from));
+
+    Ok(([(CONTENT_TYPE, "text/calendar; charset=utf-8")], feed.to_string()).into_response())
+}
@@ -1,20 +1,31 @@
 use axum::{extract, Json};
 use axum::extract::State;
 use axum_macros::debug_handler;
+use serde::Deserialize;
 use tracing::instrument;
 use sgbf_client::model::{Member, Reservation};
 use crate::server::ServerError;
-use crate::state::SharedState;
+use crate::cache::CacheRef;
 use crate::store::Uid;
 
+#[derive(Deserialize, Debug)]
+pub struct MembersQuery {
+    /// Matches name, city, role, emails and phone numbers, ignoring case and whitespace.
+    q: Option<String>,
+}
+
 #[debug_handler]
-#[instrument(skip(state), fields(user = %_uid))]
+#[instrument(skip(cache), fields(user = %_uid))]
 pub async fn get_members(
     // _client: sgbf_client::Client,
-    State(state): State<SharedState>,
+    State(cache): State<CacheRef>,
+    extract::Query(query): extract::Query<MembersQuery>,
     extract::Extension(Uid(_uid)): extract::Extension<Uid>
 ) -> Result<Json<Vec<Member>>, ServerError> {
-    let cache = state.inner.read().unwrap().cache.clone();
     let members = cache.inner.read().await.members.clone();
+    let members = match query.q.as_deref().filter(|q| !q.trim().is_empty()) {
+        Some(q) => members.into_iter().filter(|member| member.matches(q)).collect(),
+        None => members,
+    };
     Ok(Json(members))
 }
@@ -1,5 +1,6 @@
 mod reservations;
 mod calendar;
+mod duty;
 
 use std::time::Duration;
 use anyhow::Context;
@@ -12,19 +13,31 @@ use tracing::{info, instrument};
 pub use calendar::get_calendar;
 pub use calendar::get_day;
 pub use calendar::update_day;
+pub use duty::get_duty;
+pub use duty::update_duty;
+pub use duty::remove_duty;
 pub use reservations::get_reservations;
+pub use reservations::create_reservation;
+pub use reservations::update_reservation;
+pub use reservations::cancel_reservation;
 use sgbf_client::client::axum::AuthCache;
+use sgbf_client::client::ClientBuilder;
+use sgbf_client::identity::PersonId;
+use sgbf_client::model::{DayOverview, Reservation};
+use crate::cache::CacheRef;
 use crate::server::UnknownServerError;
 use crate::state::SharedState;
-use crate::store::{get_user, store_token, store_user, Uid, User};
+use crate::store::{get_user, random_secret, store_token, store_user, Uid, User};
 
 #[derive(Deserialize)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
 pub struct LoginRequest {
     username: String,
     password: String,
 }
 
 #[derive(Serialize)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
 pub struct LoginResponse {
     token: String,
 }
@@ -35,10 +48,20 @@ pub async fn login(
     State(state): State<SharedState>,
     Json(payload): Json<LoginRequest>
 ) -> Result<Json<LoginResponse>, UnknownServerError> {
-    let client = sgbf_client::Client::from_credentials(&payload.username, &payload.password).await.context("failed to create client")?;
-    let token = client.get_token();
+    let fixtures = state.inner.read().unwrap().fixtures.clone();
+    let (token, user) = match fixtures {
+        // offline, any member of the fixtures may log in by id or name
+        Some(fixtures) => {
+            let member = fixtures.get_members().await?.into_iter()
+                .find(|member| member.id == payload.username || member.name == payload.username);
+            (random_secret(), member.map(|member| member.name))
+        }
+        None => {
+            let client = ClientBuilder::from_ref(&state).login(&payload.username, &payload.password).await.context("failed to create client")?;
+            (client.get_token()?, client.get_user().await?)
+        }
+    };
     let auth_cache = AuthCache::from_ref(&state);
-    let user = client.get_user().await?;
     if let Some(user) = &user {
         info!(user.name = %user, user.id = %payload.username, "user logged in");
         let db = FirestoreDb::from_ref(&state);
@@ -66,3 +89,39 @@ pub async fn me(
     let user = get_user(&db, &uid).await?.context("failed to get user")?;
     Ok(Json(user))
 }
+
+#[derive(Serialize)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct MyEntries {
+    person_id: PersonId,
+    /// Upcoming days the user is entered on, with only their own entries.
+    days: Vec<DayOverview>,
+    reservations: Vec<Reservation>,
+}
+
+#[debug_handler]
+#[instrument(skip(state), fields(user = %uid))]
+pub async fn my_entries(
+    State(state): State<SharedState>,
+    extract::Extension(Uid(uid)): extract::Extension<Uid>
+) -> Result<Json<MyEntries>, UnknownServerError> {
+    let db = FirestoreDb::from_ref(&state);
+    let user = get_user(&db, &uid).await?.context("failed to get user")?;
+    let cache = CacheRef::from_ref(&state);
+    let calendar = cache.inner.read().await;
+    let person_id = calendar.people.person_id(&user.name);
+    let is_me = |name: &str| calendar.people.person_id(name) == person_id;
+    let days = calendar.day_overviews.iter()
+        .map(|day| DayOverview {
+            entries: day.entries.iter().filter(|entry| is_me(&entry.name)).cloned().collect(),
+            ..day.clone()
+        })
+        .filter(|day| !day.entries.is_empty())
+        .collect();
+    let reservations = calendar.reservations.iter()
+        .filter(|reservation| is_me(&reservation.reserved_by))
+        .cloned()
+        .collect();
+    Ok(Json(MyEntries { person_id, days, reservations }))
+}
@@ -1,11 +1,11 @@
-use axum_macros::debug_handler;
 use tracing::instrument;
 use axum::{extract, Json};
 use axum::extract::State;
 use serde::Deserialize;
-use sgbf_client::model::{Day, DayOverview, Overlaps};
+use sgbf_client::model::{Day, DayOverview, EntryType, Overlaps};
+use sgbf_client::model::query::{self, TimeBracket};
 use crate::server::{ServerError, UnknownServerError};
-use crate::state::SharedState;
+use crate::state::{Roster, SharedState};
 use crate::store::Uid;
 
 fn default_calendar_limit() -> usize {
@@ -13,21 +13,32 @@ fn default_calendar_limit() -> usize {
 }
 
 #[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
 pub struct CalendarQuery {
     #[serde(default = "default_calendar_limit")]
-    limit: usize
+    limit: usize,
+    #[serde(default)]
+    time_bracket: TimeBracket,
+    event_type: Option<EntryType>,
 }
 
-#[debug_handler]
-#[instrument(skip(state), fields(limit = %query.limit, user = %_uid))]
+#[instrument(skip(roster, state), fields(limit = %query.limit, user = %_uid))]
 pub async fn get_calendar(
-    // _client: sgbf_client::Client,
+    Roster(roster): Roster,
     extract::Query(query): extract::Query<CalendarQuery>,
     State(state): State<SharedState>,
     extract::Extension(Uid(_uid)): extract::Extension<Uid>
 ) -> Result<Json<Vec<DayOverview>>, ServerError> {
     let cache = state.inner.read().unwrap().cache.clone();
-    let calendar = cache.inner.read().await.day_overviews.clone();
+    // the cache only holds upstream's default window
+    let calendar = if query.time_bracket == TimeBracket::Upcoming {
+        cache.inner.read().await.day_overviews.clone()
+    } else {
+        roster.get_calendar(&query::CalendarQuery {
+            time_bracket: query.time_bracket,
+            event_type: query.event_type.clone(),
+        }).await?
+    };
     // only the first `limit` days
     let calendar: Vec<_> = calendar.into_iter().take(query.limit).collect();
     let reservations = cache.inner.read().await.reservations.clone();
@@ -37,8 +48,12 @@ pub async fn get_calendar(
         let reservations = reservations.iter().filter(|reservation| {
             reservation.period.overlaps(&day.date)
         }).cloned().collect();
+        let entries = day.entries.into_iter().filter(|entry| {
+            query.event_type.as_ref().is_none_or(|event_type| &entry.entry_type == event_type)
+        }).collect();
         DayOverview {
             reservations: Some(reservations),
+            entries,
             ..day
         }
     }).collect();
@@ -57,11 +72,11 @@ pub struct GetDayQuery {
 }
 
 pub async fn get_day(
-    client: sgbf_client::Client,
+    Roster(roster): Roster,
     extract::Query(query): extract::Query<GetDayQuery>,
     State(state): State<SharedState>,
 ) -> Result<Json<Day>, ServerError> {
-    let mut day = client.get_day(query.date).await?;
+    let mut day = roster.get_day(query.date).await?;
     let cache = state.inner.read().unwrap().cache.clone();
     let reservations = cache.inner.read().await.reservations.clone();
     day.reservations = Some(reservations.iter().filter(|reservation| {
@@ -71,13 +86,12 @@ pub async fn get_day(
 }
 
 pub async fn update_day(
-    client: sgbf_client::Client,
+    Roster(roster): Roster,
     State(state): State<SharedState>,
     extract::Query(query): extract::Query<GetDayQuery>,
     extract::Json(payload): extract::Json<Day>
 ) -> Result<Json<Day>, UnknownServerError> {
     // check and update notification settings
-    client.update_day(query.date, payload).await;
-    let day = client.get_day(query.date).await?;
+    let day = roster.update_day(query.date, payload).await?;
     Ok(Json(day))
 }
@@ -0,0 +1,54 @@
+use axum::{extract, Json};
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Deserialize;
+use tracing::instrument;
+use sgbf_client::model::{Duty, ParticipantType};
+use crate::server::ServerError;
+use crate::state::{Roster, SharedState};
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DutyQuery {
+    date: chrono::NaiveDate,
+    participant_type: ParticipantType,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdateDutyQuery {
+    date: chrono::NaiveDate,
+}
+
+#[instrument(skip(roster))]
+pub async fn get_duty(
+    Roster(roster): Roster,
+    extract::Query(query): extract::Query<DutyQuery>,
+) -> Result<Json<Duty>, ServerError> {
+    let duty = roster.get_duty(query.date, query.participant_type).await?;
+    Ok(Json(duty))
+}
+
+#[instrument(skip(roster, state, payload))]
+pub async fn update_duty(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Query(query): extract::Query<UpdateDutyQuery>,
+    extract::Json(payload): extract::Json<Duty>
+) -> Result<Json<Duty>, ServerError> {
+    let duty = roster.update_duty(query.date, payload).await?;
+    let cache = state.inner.read().unwrap().cache.clone();
+    cache.mark_dirty().await;
+    Ok(Json(duty))
+}
+
+#[instrument(skip(roster, state))]
+pub async fn remove_duty(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Query(query): extract::Query<DutyQuery>,
+) -> Result<StatusCode, ServerError> {
+    roster.remove_duty(query.date, query.participant_type).await?;
+    let cache = state.inner.read().unwrap().cache.clone();
+    cache.mark_dirty().await;
+    Ok(StatusCode::NO_CONTENT)
+}
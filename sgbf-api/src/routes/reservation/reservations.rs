@@ -1,20 +1,91 @@
 use axum::{extract, Json};
 use axum::extract::State;
-use axum_macros::debug_handler;
+use axum::http::StatusCode;
 use tracing::instrument;
-use sgbf_client::model::{Reservation};
+use sgbf_client::model::{Overlaps, Reservation, ReservationRequest};
+use sgbf_client::model::query::{ReservationQuery, ReservationTimeframe};
 use crate::server::ServerError;
-use crate::state::SharedState;
+use crate::state::{Roster, SharedState};
 use crate::store::Uid;
 
-#[debug_handler]
-#[instrument(skip(state), fields(user = %_uid))]
+#[instrument(skip(roster, state), fields(user = %_uid))]
 pub async fn get_reservations(
-    // _client: sgbf_client::Client,
+    Roster(roster): Roster,
+    extract::Query(query): extract::Query<ReservationQuery>,
     State(state): State<SharedState>,
     extract::Extension(Uid(_uid)): extract::Extension<Uid>
 ) -> Result<Json<Vec<Reservation>>, ServerError> {
+    // the cache holds the upcoming reservations of all aircraft
+    if query.timeframe != ReservationTimeframe::Upcoming {
+        return Ok(Json(roster.get_reservations(&query).await?));
+    }
     let cache = state.inner.read().unwrap().cache.clone();
-    let reservations = cache.inner.read().await.reservations.clone();
+    let reservations = cache.inner.read().await.reservations.iter()
+        .filter(|reservation| {
+            query.aircraft.as_ref().is_none_or(|aircraft| &reservation.plane.registration_number == aircraft)
+        })
+        .cloned()
+        .collect();
     Ok(Json(reservations))
 }
+
+#[instrument(skip(roster, state, payload), fields(user = %_uid))]
+pub async fn create_reservation(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Extension(Uid(_uid)): extract::Extension<Uid>,
+    extract::Json(payload): extract::Json<ReservationRequest>
+) -> Result<Json<Reservation>, ServerError> {
+    let cache = state.inner.read().unwrap().cache.clone();
+    check_conflicts(&cache.inner.read().await.reservations, &payload, None)?;
+    let reservation = roster.create_reservation(&payload).await?;
+    cache.mark_dirty().await;
+    Ok(Json(reservation))
+}
+
+#[instrument(skip(roster, state, payload), fields(user = %_uid))]
+pub async fn update_reservation(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Extension(Uid(_uid)): extract::Extension<Uid>,
+    extract::Path(id): extract::Path<i32>,
+    extract::Json(payload): extract::Json<ReservationRequest>
+) -> Result<Json<Reservation>, ServerError> {
+    let cache = state.inner.read().unwrap().cache.clone();
+    check_conflicts(&cache.inner.read().await.reservations, &payload, Some(id))?;
+    let reservation = roster.update_reservation(id, &payload).await?;
+    cache.mark_dirty().await;
+    Ok(Json(reservation))
+}
+
+#[instrument(skip(roster, state), fields(user = %_uid))]
+pub async fn cancel_reservation(
+    Roster(roster): Roster,
+    State(state): State<SharedState>,
+    extract::Extension(Uid(_uid)): extract::Extension<Uid>,
+    extract::Path(id): extract::Path<i32>,
+) -> Result<StatusCode, ServerError> {
+    roster.cancel_reservation(id).await?;
+    let cache = state.inner.read().unwrap().cache.clone();
+    cache.mark_dirty().await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Rejects empty periods and periods overlapping another reservation of the same aircraft, as far
+/// as the cached reservations tell.
+fn check_conflicts(reservations: &[Reservation], request: &ReservationRequest, id: Option<i32>) -> Result<(), ServerError> {
+    if request.period.is_empty() {
+        return Err(ServerError::InvalidRequest(format!("empty reservation period: {}", request.period)));
+    }
+    let conflict = reservations.iter()
+        .filter(|reservation| Some(reservation.id) != id)
+        .filter(|reservation| reservation.plane.registration_number == request.plane.registration_number)
+        .find(|reservation| reservation.period.overlaps(&request.period));
+    match conflict {
+        Some(reservation) => Err(ServerError::Conflict(format!(
+            "{} is already reserved by {} for {}",
+            reservation.plane.registration_number, reservation.reserved_by, reservation.period
+        ))),
+        None => Ok(()),
+    }
+}
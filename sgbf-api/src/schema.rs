@@ -0,0 +1,65 @@
+//! The frontend's model definitions, generated from the types the API serializes.
+//!
+//! Regenerate with `cargo run -p sgbf-api --features schema --bin schema` after changing any of
+//! them, a test fails while the committed output is stale.
+
+use std::path::{Path, PathBuf};
+use anyhow::Context;
+use sgbf_client::schema::Definitions;
+use crate::routes::feed::FeedResponse;
+use crate::routes::reservation::{LoginRequest, LoginResponse, MyEntries};
+use crate::store::User;
+
+const HEADER: &str = "// Generated by `cargo run -p sgbf-api --features schema --bin schema`, do not edit.\n\n";
+
+pub fn definitions() -> Definitions {
+    let mut definitions = Definitions::model();
+    definitions
+        .add::<User>()
+        .add::<LoginRequest>()
+        .add::<LoginResponse>()
+        .add::<MyEntries>()
+        .add::<FeedResponse>();
+    definitions
+}
+
+pub fn typescript() -> String {
+    format!("{}{}", HEADER, definitions().typescript())
+}
+
+pub fn json_schema() -> String {
+    definitions().json_schema()
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../frontend/src/generated")
+}
+
+pub fn typescript_path() -> PathBuf {
+    output_dir().join("model.ts")
+}
+
+pub fn json_schema_path() -> PathBuf {
+    output_dir().join("model.schema.json")
+}
+
+/// Writes both files to the frontend.
+pub fn write() -> anyhow::Result<()> {
+    for (path, contents) in [(typescript_path(), typescript()), (json_schema_path(), json_schema())] {
+        std::fs::write(&path, contents).with_context(|| format!("could not write {}", path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_up_to_date() {
+        for (path, contents) in [(typescript_path(), typescript()), (json_schema_path(), json_schema())] {
+            let committed = std::fs::read_to_string(&path).unwrap_or_default();
+            assert!(committed == contents, "{} is stale, regenerate it with `cargo run -p sgbf-api --features schema --bin schema`", path.display());
+        }
+    }
+}
@@ -12,7 +12,7 @@ use axum::http::{Method, StatusCode};
 use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
 use axum::middleware::from_fn_with_state;
 use axum::response::{IntoResponse, Response};
-use axum::routing::{get, post};
+use axum::routing::{get, post, put};
 use axum_client_ip::SecureClientIpSource;
 use firestore::FirestoreDb;
 use onesignal_rust_api::apis::configuration::Configuration;
@@ -22,9 +22,15 @@ use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::LatencyUnit;
 use tower_http::trace::{DefaultOnFailure, DefaultOnRequest, DefaultOnResponse, TraceLayer};
-use tracing::{error, info, Level, Span};
+use tracing::{error, info, Level, Span, warn};
 use routes::members;
+use routes::aircraft;
+use routes::feed;
 use sgbf_client::client::axum::AuthCache;
+use sgbf_client::client::ClientError;
+use sgbf_client::limiter::RateLimiter;
+use sgbf_client::source::{FixtureSource, HttpSource, RosterSource};
+use sgbf_client::transport::Transport;
 use crate::cache::Cache;
 use crate::config::{Config, OneSignal};
 use crate::{onesignal, routes};
@@ -37,10 +43,29 @@ pub async fn init_default_server() -> anyhow::Result<()> {
     let _guard = crate::tracing::init_tracing(&config.tracing)?;
 
     let db = FirestoreDb::new(&config.firebase.project).await?;
-    let notifications = onesignal::create_onesignal_configuration(&config.onesignal);
+    let notifier = onesignal::create_notifier(&config.onesignal);
 
-    let auth_cache = AuthCache::new();
-    let cache = Arc::new(Cache::new(db.clone(), &config.cache, notifications));
+    let transport = match (&config.upstream.replay, &config.upstream.record) {
+        (Some(dir), _) => Transport::replay(dir),
+        (None, Some(dir)) => Transport::record(dir),
+        (None, None) => Transport::Network,
+    };
+    let upstream = sgbf_client::Client::builder()
+        .transport(transport)
+        .rate_limiter(RateLimiter::new(config.upstream.rate_limit))
+        .retry_policy(config.upstream.retry);
+    let fixtures = config.upstream.fixtures.as_ref().map(|dir| {
+        warn!(dir = %dir.display(), "serving fixtures instead of upstream");
+        Arc::new(FixtureSource::new(dir)) as Arc<dyn RosterSource>
+    });
+    // without upstream to check tokens against, only those issued by `/login` are valid
+    let auth_cache = if fixtures.is_some() { AuthCache::offline() } else { AuthCache::new() };
+    let source = fixtures.clone().unwrap_or_else(|| Arc::new(HttpSource::new(
+        upstream.clone().concurrency(config.cache.concurrency),
+        &config.cache.username,
+        &config.cache.password,
+    )));
+    let cache = Arc::new(Cache::new(&config.cache, source, notifier));
     let cache_handle = {
         let cache = cache.clone();
         info!("starting cache polling");
@@ -59,7 +84,9 @@ pub async fn init_default_server() -> anyhow::Result<()> {
         auth_cache,
         config: config.clone(),
         cache: cache.clone(),
-        db: db.clone()
+        db: db.clone(),
+        upstream,
+        fixtures,
     });
     _ = init_server(&config, state).await;
     info!("shutting down cache polling");
@@ -71,7 +98,7 @@ pub async fn init_default_server() -> anyhow::Result<()> {
 
 pub async fn init_server(cfg: &Config, state: SharedState) -> anyhow::Result<()> {
     let cors = CorsLayer::new()
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_headers(vec![
             CONTENT_TYPE,
             AUTHORIZATION,
@@ -88,18 +115,34 @@ pub async fn init_server(cfg: &Config, state: SharedState) -> anyhow::Result<()>
         .route("/reservation/calendar", get(reservation::get_calendar)
             .layer(auth_service.to_owned())
         )
-        .route("/reservation/reservations", get(reservation::get_reservations)
+        .route("/reservation/reservations", get(reservation::get_reservations).post(reservation::create_reservation)
+            .layer(auth_service.to_owned())
+        )
+        .route("/reservation/reservations/:id", put(reservation::update_reservation).delete(reservation::cancel_reservation)
             .layer(auth_service.to_owned())
         )
         .route("/members", get(members::get_members)
             .layer(auth_service.to_owned())
         )
+        .route("/aircraft", get(aircraft::get_aircraft)
+            .layer(auth_service.to_owned())
+        )
         .route("/reservation/@me", get(routes::reservation::me)
             .layer(auth_service.to_owned())
         )
+        .route("/reservation/@me/entries", get(routes::reservation::my_entries)
+            .layer(auth_service.to_owned())
+        )
+        .route("/reservation/@me/feed", post(feed::create_feed).delete(feed::revoke_feed)
+            .layer(auth_service.to_owned())
+        )
+        .route("/feed/:secret", get(feed::get_feed))
         .route("/reservation/day", get(reservation::get_day).post(reservation::update_day)
             .layer(auth_service.to_owned())
         )
+        .route("/reservation/duty", get(reservation::get_duty).post(reservation::update_duty).delete(reservation::remove_duty)
+            .layer(auth_service.to_owned())
+        )
         .layer(
             ServiceBuilder::new()
                 // Handle errors from middleware
@@ -162,6 +205,8 @@ async fn handle_error(error: BoxError) -> impl IntoResponse {
 
 pub enum ServerError {
     InvalidToken,
+    InvalidRequest(String),
+    Conflict(String),
     Unknown(UnknownServerError),
 }
 
@@ -169,6 +214,8 @@ impl IntoResponse for ServerError {
     fn into_response(self) -> Response {
         match self {
             Self::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
+            Self::InvalidRequest(message) => (StatusCode::UNPROCESSABLE_ENTITY, message).into_response(),
+            Self::Conflict(message) => (StatusCode::CONFLICT, message).into_response(),
             Self::Unknown(err) => err.into_response(),
         }
     }
@@ -178,6 +225,14 @@ pub struct UnknownServerError(anyhow::Error);
 
 impl IntoResponse for UnknownServerError {
     fn into_response(self) -> Response {
+        if let Some(err) = self.0.downcast_ref::<ClientError>() {
+            let status = client_error_status(err);
+            if status.is_server_error() {
+                error!("Upstream error: {}", self.0);
+                self.0.chain().for_each(|cause| error!("caused by: {}", cause));
+            }
+            return (status, err.to_string()).into_response();
+        }
         error!("Unknown server error: {}", self.0);
         self.0.chain().for_each(|cause| error!("caused by: {}", cause));
         (
@@ -188,6 +243,17 @@ impl IntoResponse for UnknownServerError {
     }
 }
 
+fn client_error_status(err: &ClientError) -> StatusCode {
+    match err {
+        ClientError::SessionExpired => StatusCode::UNAUTHORIZED,
+        ClientError::Maintenance => StatusCode::SERVICE_UNAVAILABLE,
+        ClientError::WriteRejected(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        ClientError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+        ClientError::Network(_) | ClientError::Status(_) | ClientError::Parse(_) | ClientError::Unrecorded(_) => StatusCode::BAD_GATEWAY,
+        ClientError::Configuration(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 impl<E> From<E> for ServerError
     where
         E: Into<anyhow::Error>,
@@ -1,7 +1,12 @@
 use std::sync::{Arc, RwLock};
-use axum::extract::FromRef;
+use axum::async_trait;
+use axum::extract::{FromRef, FromRequestParts};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
 use firestore::FirestoreDb;
 use sgbf_client::client::axum::{AuthCache};
+use sgbf_client::client::ClientBuilder;
+use sgbf_client::source::RosterSource;
 use crate::cache::{Cache, CacheRef};
 use crate::config::Config;
 
@@ -21,7 +26,11 @@ pub struct AppState {
     pub(crate) auth_cache: AuthCache,
     pub(crate) config: Config,
     pub(crate) cache: CacheRef,
-    pub(crate) db: FirestoreDb
+    pub(crate) db: FirestoreDb,
+    /// Template for upstream clients, sharing one connection pool and rate limiter.
+    pub(crate) upstream: ClientBuilder,
+    /// Replaces upstream for all requests when running offline.
+    pub(crate) fixtures: Option<Arc<dyn RosterSource>>,
 }
 
 impl FromRef<SharedState> for AppState {
@@ -46,4 +55,28 @@ impl FromRef<SharedState> for FirestoreDb {
     fn from_ref(input: &SharedState) -> Self {
         input.inner.read().unwrap().db.clone()
     }
-}
\ No newline at end of file
+}
+
+impl FromRef<SharedState> for ClientBuilder {
+    fn from_ref(input: &SharedState) -> Self {
+        input.inner.read().unwrap().upstream.clone()
+    }
+}
+
+/// The roster as seen by the current user: their own upstream session, or the fixtures when
+/// running offline.
+pub struct Roster(pub Arc<dyn RosterSource>);
+
+#[async_trait]
+impl FromRequestParts<SharedState> for Roster {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &SharedState) -> Result<Self, Self::Rejection> {
+        let fixtures = state.inner.read().unwrap().fixtures.clone();
+        if let Some(fixtures) = fixtures {
+            return Ok(Roster(fixtures));
+        }
+        let client = sgbf_client::Client::from_request_parts(parts, state).await?;
+        Ok(Roster(Arc::new(client)))
+    }
+}
@@ -4,7 +4,8 @@ use axum::extract::{FromRef, State};
 use axum::http;
 use axum::http::StatusCode;
 use chrono::Utc;
-use firestore::{FirestoreDb, FirestoreQueryCollection, FirestoreTimestamp, path};
+use firestore::{FirestoreDb, FirestoreQueryCollection, FirestoreTimestamp, path, path_camel_case};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use tracing::{debug, warn};
@@ -12,6 +13,7 @@ use sgbf_client::client::axum::{AuthCache, AuthState};
 use crate::server::ServerError::Unknown;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct User {
     pub id: String,
@@ -20,12 +22,14 @@ pub struct User {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct UserSettings {
     pub notifications: NotificationSettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[cfg_attr(any(test, feature = "schema"), derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct NotificationSettings {
     pub enabled: bool,
@@ -70,11 +74,15 @@ pub async fn clean_expired_tokens(db: &FirestoreDb) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub async fn store_token(db: &FirestoreDb, token: &str, user_id: &str) -> anyhow::Result<TokenBinding> {
+/// Tokens and feed secrets are only stored as their hash.
+fn hash_secret(secret: &str) -> String {
     let mut hasher = Sha256::default();
-    hasher.update(token);
-    let hash = hasher.finalize();
-    let hash = format!("{:x}", hash);
+    hasher.update(secret);
+    format!("{:x}", hasher.finalize())
+}
+
+pub async fn store_token(db: &FirestoreDb, token: &str, user_id: &str) -> anyhow::Result<TokenBinding> {
+    let hash = hash_secret(token);
     let result = db.fluent()
         .update()
         .in_col("tokens")
@@ -90,10 +98,7 @@ pub async fn store_token(db: &FirestoreDb, token: &str, user_id: &str) -> anyhow
 }
 
 pub async fn get_uid_for_token(db: &FirestoreDb, token: &str) -> anyhow::Result<Option<String>> {
-    let mut hasher = Sha256::default();
-    hasher.update(token);
-    let hash = hasher.finalize();
-    let hash = format!("{:x}", hash);
+    let hash = hash_secret(token);
     let result = db.fluent()
         .select()
         .by_id_in("tokens")
@@ -104,6 +109,82 @@ pub async fn get_uid_for_token(db: &FirestoreDb, token: &str) -> anyhow::Result<
     Ok(binding.map(|binding| binding.user_id))
 }
 
+/// Grants read access to a user's calendar feed to whoever knows the secret, until it is
+/// revoked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedBinding {
+    #[serde(alias = "_firestore_id")]
+    id: Option<String>,
+    pub user_id: String,
+    #[serde(with = "firestore::serialize_as_timestamp")]
+    pub created: chrono::DateTime<chrono::Utc>,
+}
+
+/// 32 random bytes, hex encoded.
+pub fn random_secret() -> String {
+    rand::thread_rng().r#gen::<[u8; 32]>().iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Creates a new feed secret for the user, revoking any previous one.
+pub async fn create_feed(db: &FirestoreDb, user_id: &str) -> anyhow::Result<String> {
+    revoke_feeds(db, user_id).await?;
+    let secret = random_secret();
+    let result = db.fluent()
+        .update()
+        .in_col("feeds")
+        .document_id(&hash_secret(&secret))
+        .object(&FeedBinding {
+            id: None,
+            user_id: user_id.to_string(),
+            created: chrono::Utc::now(),
+        })
+        .execute::<FeedBinding>()
+        .await;
+    result.context("could not save feed binding")?;
+    Ok(secret)
+}
+
+pub async fn revoke_feeds(db: &FirestoreDb, user_id: &str) -> anyhow::Result<()> {
+    let feeds: Vec<FeedBinding> = db.fluent()
+        .select()
+        .from("feeds")
+        .filter(|q| {
+            q.field(path_camel_case!(FeedBinding::user_id)).eq(user_id)
+        })
+        .obj()
+        .query()
+        .await?;
+    debug!("revoking {} feeds", feeds.len());
+    for feed in feeds {
+        let Some(id) = feed.id else {
+            warn!("skipping feed binding without an id");
+            continue;
+        };
+        let result = db.fluent()
+            .delete()
+            .from("feeds")
+            .document_id(&id)
+            .execute()
+            .await;
+        result.context("could not delete feed binding")?;
+    }
+    Ok(())
+}
+
+pub async fn get_uid_for_feed(db: &FirestoreDb, secret: &str) -> anyhow::Result<Option<String>> {
+    let result = db.fluent()
+        .select()
+        .by_id_in("feeds")
+        .obj()
+        .one(&hash_secret(secret))
+        .await;
+    let binding: Option<FeedBinding> = result.context("could not get feed binding")?;
+    Ok(binding.map(|binding| binding.user_id))
+}
+
 #[derive(Debug, Clone)]
 pub struct Uid(pub String);
 
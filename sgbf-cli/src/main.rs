@@ -0,0 +1,241 @@
+//! `sgbf`, the roster from the command line.
+//!
+//! Log in once with `sgbf login -u <username>`, the session is saved for the other commands. All
+//! commands print a table by default, `--format json` prints the model types as the API serves
+//! them and `--format csv` the table as CSV.
+
+mod output;
+mod session;
+
+use std::io::BufRead;
+use anyhow::{bail, Context};
+use chrono::NaiveDate;
+use clap::{Arg, ArgGroup, ArgMatches, Command};
+use sgbf_client::client::blocking::Client;
+use sgbf_client::client::{ClientBuilder, ClientError};
+use sgbf_client::model::{Day, DayOverview, EntryType, Member, Reservation, RosterEntryType};
+use sgbf_client::model::query::{CalendarQuery, ReservationQuery, TimeBracket};
+use crate::output::{Format, Table};
+
+fn cli() -> Command<'static> {
+    Command::new("sgbf")
+        .about("Roster, sign-ups and reservations from the command line")
+        .subcommand_required(true)
+        .arg(Arg::new("format").long("format").short('f').global(true).takes_value(true)
+            .value_parser(["table", "json", "csv"]).default_value("table")
+            .help("Output format"))
+        .arg(Arg::new("base-url").long("base-url").global(true).takes_value(true)
+            .help("Upstream site, e.g. a local mirror"))
+        .subcommand(Command::new("login")
+            .about("Logs in and saves the session for the other commands")
+            .arg(Arg::new("username").long("username").short('u').takes_value(true).required(true))
+            .arg(Arg::new("password").long("password").short('p').takes_value(true)
+                .help("Read from SGBF_PASSWORD or stdin if not given")))
+        .subcommand(Command::new("calendar")
+            .about("Lists the days in the roster calendar")
+            .arg(Arg::new("time-bracket").long("time-bracket").takes_value(true)
+                .help("upcoming (default), past or a year")))
+        .subcommand(Command::new("day")
+            .about("Shows who signed up for a day")
+            .arg(Arg::new("date").required(true).help("YYYY-MM-DD")))
+        .subcommand(Command::new("signup")
+            .about("Signs up for a day, or changes the sign-up")
+            .arg(Arg::new("date").required(true).help("YYYY-MM-DD"))
+            .arg(Arg::new("definite").long("definite").help("Sign up"))
+            .arg(Arg::new("tentative").long("tentative").help("Sign up tentatively"))
+            .arg(Arg::new("unavailable").long("unavailable").help("Mark as unavailable"))
+            .group(ArgGroup::new("status").args(&["definite", "tentative", "unavailable"]).required(true))
+            .arg(Arg::new("remark").long("remark").takes_value(true).help("Remark shown next to the name, keeps the current one if not given")))
+        .subcommand(Command::new("reservations")
+            .about("Lists aircraft reservations")
+            .arg(Arg::new("aircraft").long("aircraft").takes_value(true).help("Registration, e.g. HB-3187")))
+        .subcommand(Command::new("members")
+            .about("Lists the member directory")
+            .arg(Arg::new("search").long("search").short('s').takes_value(true)
+                .help("Matches name, city, role, emails and phone numbers")))
+}
+
+fn main() -> anyhow::Result<()> {
+    let matches = cli().get_matches();
+    let format = matches.value_of("format").unwrap_or("table").parse::<Format>()?;
+    let mut builder = ClientBuilder::new();
+    if let Some(base_url) = matches.value_of("base-url") {
+        builder = builder.base_url(base_url);
+    }
+    let result = match matches.subcommand() {
+        Some(("login", args)) => login(builder, args),
+        Some((command, args)) => {
+            let client = Client::with_token(builder, &session::load()?)?;
+            match command {
+                "calendar" => calendar(&client, format, args),
+                "day" => day(&client, format, args),
+                "signup" => signup(&client, format, args),
+                "reservations" => reservations(&client, format, args),
+                "members" => members(&client, format, args),
+                _ => unreachable!("subcommands are defined in cli()"),
+            }
+        }
+        None => unreachable!("a subcommand is required"),
+    };
+    match result {
+        Err(error) if matches!(error.downcast_ref(), Some(ClientError::SessionExpired)) => {
+            bail!("session expired, run `sgbf login` again")
+        }
+        result => result,
+    }
+}
+
+fn login(builder: ClientBuilder, args: &ArgMatches) -> anyhow::Result<()> {
+    let username = args.value_of("username").context("username is required")?;
+    let password = match args.value_of("password").map(str::to_string).or_else(|| std::env::var("SGBF_PASSWORD").ok()) {
+        Some(password) => password,
+        None => {
+            eprint!("password for {}: ", username);
+            let mut password = String::new();
+            std::io::stdin().lock().read_line(&mut password)?;
+            password.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+    let client = Client::login(builder, username, &password)?;
+    let Some(user) = client.get_user()? else {
+        bail!("login failed, check username and password");
+    };
+    let path = session::store(&client.get_token()?)?;
+    eprintln!("logged in as {}, session saved to {}", user, path.display());
+    Ok(())
+}
+
+fn date(args: &ArgMatches) -> anyhow::Result<NaiveDate> {
+    let date = args.value_of("date").context("date is required")?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").with_context(|| format!("invalid date {}, expected YYYY-MM-DD", date))
+}
+
+fn entry_type(entry_type: &EntryType) -> &str {
+    match entry_type {
+        EntryType::FlightInstructor => "flight instructor",
+        EntryType::TowingPilot => "tow pilot",
+        EntryType::WinchOperator => "winch operator",
+        EntryType::Other(code) => code,
+    }
+}
+
+fn roster_entry_type(entry_type: Option<RosterEntryType>) -> &'static str {
+    match entry_type {
+        Some(RosterEntryType::Definite) => "definite",
+        Some(RosterEntryType::Tentative) => "tentative",
+        Some(RosterEntryType::Unavailable) => "unavailable",
+        None => "",
+    }
+}
+
+fn calendar(client: &Client, format: Format, args: &ArgMatches) -> anyhow::Result<()> {
+    let time_bracket = args.value_of("time-bracket")
+        .map(str::parse::<TimeBracket>)
+        .transpose()?
+        .unwrap_or_default();
+    let calendar = client.get_calendar(&CalendarQuery { time_bracket, event_type: None })?;
+    output::print(format, &calendar, |calendar: &Vec<DayOverview>| {
+        let mut table = Table::new(&["Date", "Definite", "Tentative", "Duties", "Note"]);
+        for day in calendar {
+            let duties = day.entries.iter()
+                .map(|entry| format!("{} ({}, {})", entry.name, entry_type(&entry.entry_type), entry.time_frame))
+                .collect::<Vec<_>>();
+            table.row([
+                day.date.format("%a %Y-%m-%d").to_string(),
+                day.registered_pilots.definitive.to_string(),
+                day.registered_pilots.tentative.to_string(),
+                duties.join("; "),
+                day.note.clone().unwrap_or_default(),
+            ]);
+        }
+        table
+    })
+}
+
+fn day_table(day: &Day) -> Table {
+    let mut table = Table::new(&["Name", "Status", "Message"]);
+    for entry in &day.entries {
+        table.row([entry.name.clone(), roster_entry_type(Some(entry.entry_type)).to_string(), entry.message.clone()]);
+    }
+    table
+}
+
+fn day(client: &Client, format: Format, args: &ArgMatches) -> anyhow::Result<()> {
+    let day = client.get_day(date(args)?)?;
+    output::print(format, &day, day_table)
+}
+
+fn signup(client: &Client, format: Format, args: &ArgMatches) -> anyhow::Result<()> {
+    let date = date(args)?;
+    let entry_type = match () {
+        _ if args.is_present("definite") => RosterEntryType::Definite,
+        _ if args.is_present("tentative") => RosterEntryType::Tentative,
+        _ => RosterEntryType::Unavailable,
+    };
+    let day = client.get_day(date)?;
+    let remarks = args.value_of("remark").map(str::to_string).or(day.remarks.clone());
+    let day = client.update_day(date, Day { entry_type: Some(entry_type), remarks, ..day })?;
+    eprintln!("signed up as {} on {}", roster_entry_type(day.entry_type), date);
+    output::print(format, &day, day_table)
+}
+
+fn reservations(client: &Client, format: Format, args: &ArgMatches) -> anyhow::Result<()> {
+    let query = ReservationQuery {
+        aircraft: args.value_of("aircraft").map(str::to_string),
+        ..Default::default()
+    };
+    let reservations = client.get_reservations(&query)?;
+    output::print(format, &reservations, |reservations: &Vec<Reservation>| {
+        let mut table = Table::new(&["ID", "Aircraft", "From", "To", "Reserved by", "Comments"]);
+        for reservation in reservations {
+            table.row([
+                reservation.id.to_string(),
+                reservation.plane.to_string(),
+                reservation.period.from.format("%Y-%m-%d %H:%M").to_string(),
+                reservation.period.to.format("%Y-%m-%d %H:%M").to_string(),
+                reservation.reserved_by.clone(),
+                reservation.comments.join(" / "),
+            ]);
+        }
+        table
+    })
+}
+
+fn members(client: &Client, format: Format, args: &ArgMatches) -> anyhow::Result<()> {
+    let mut members = client.get_members()?;
+    if let Some(search) = args.value_of("search") {
+        members.retain(|member| member.matches(search));
+    }
+    output::print(format, &members, |members: &Vec<Member>| {
+        let mut table = Table::new(&["Name", "Phone", "Mobile", "Email", "City", "Role"]);
+        for member in members {
+            let contact = |field: fn(&sgbf_client::model::Addresses) -> &Option<String>| {
+                field(&member.private).clone().or_else(|| field(&member.office).clone()).unwrap_or_default()
+            };
+            table.row([
+                member.name.clone(),
+                contact(|addresses| &addresses.phone),
+                contact(|addresses| &addresses.mobile),
+                contact(|addresses| &addresses.email),
+                member.address.as_ref().and_then(|address| address.city.clone()).unwrap_or_default(),
+                member.role.clone().unwrap_or_default(),
+            ]);
+        }
+        table
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        cli().debug_assert();
+        let matches = cli().try_get_matches_from(["sgbf", "signup", "2023-06-04", "--tentative", "--remark", "ab Mittag"]).unwrap();
+        let (_, args) = matches.subcommand().unwrap();
+        assert_eq!(date(args).unwrap(), NaiveDate::from_ymd_opt(2023, 6, 4).unwrap());
+        assert!(cli().try_get_matches_from(["sgbf", "signup", "2023-06-04", "--definite", "--tentative"]).is_err());
+        assert!(cli().try_get_matches_from(["sgbf", "signup", "2023-06-04"]).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! Prints results as an aligned table, as JSON of the model types or as CSV.
+
+use std::fmt::Write;
+use std::str::FromStr;
+use anyhow::bail;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => bail!("unknown format {}, expected table, json or csv", s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    headers: &'static [&'static str],
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &'static [&'static str]) -> Self {
+        Self { headers, rows: vec![] }
+    }
+
+    pub fn row(&mut self, cells: impl IntoIterator<Item = String>) {
+        self.rows.push(cells.into_iter().collect());
+    }
+
+    fn render(&self) -> String {
+        let mut widths = self.headers.iter().map(|header| header.chars().count()).collect::<Vec<_>>();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let line = |cells: &mut dyn Iterator<Item = &str>| {
+            let line = cells.zip(&widths)
+                .map(|(cell, width)| format!("{:width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join("  ");
+            line.trim_end().to_string()
+        };
+        let mut out = String::new();
+        let _ = writeln!(out, "{}", line(&mut self.headers.iter().copied()));
+        let dashes = widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>();
+        let _ = writeln!(out, "{}", line(&mut dashes.iter().map(String::as_str)));
+        for row in &self.rows {
+            let _ = writeln!(out, "{}", line(&mut row.iter().map(String::as_str)));
+        }
+        out
+    }
+
+    fn csv(&self) -> String {
+        let mut out = String::new();
+        let record = |cells: &mut dyn Iterator<Item = &str>| cells.map(csv_field).collect::<Vec<_>>().join(",");
+        let _ = writeln!(out, "{}", record(&mut self.headers.iter().copied()));
+        for row in &self.rows {
+            let _ = writeln!(out, "{}", record(&mut row.iter().map(String::as_str)));
+        }
+        out
+    }
+}
+
+// RFC 4180 quoting
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Prints `value` as JSON or the table built from it.
+pub fn print<T: Serialize>(format: Format, value: &T, table: impl FnOnce(&T) -> Table) -> anyhow::Result<()> {
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        Format::Table => print!("{}", table(value).render()),
+        Format::Csv => print!("{}", table(value).csv()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut table = Table::new(&["Name", "Message"]);
+        table.row(["Muster Hans".to_string(), "ab 10:00, mit Auto".to_string()]);
+        table.row(["Egli Eva".to_string(), "sagt \"vielleicht\"".to_string()]);
+        assert_eq!(table.render(), "\
+Name         Message
+-----------  ------------------
+Muster Hans  ab 10:00, mit Auto
+Egli Eva     sagt \"vielleicht\"
+");
+        assert_eq!(table.csv(), "\
+Name,Message
+Muster Hans,\"ab 10:00, mit Auto\"
+Egli Eva,\"sagt \"\"vielleicht\"\"\"
+");
+    }
+}
@@ -0,0 +1,41 @@
+//! The session token saved by `sgbf login`, in `$XDG_CONFIG_HOME/sgbf/session` or
+//! `~/.config/sgbf/session`. `SGBF_TOKEN` takes precedence, e.g. for cron jobs.
+
+use std::io::Write;
+use std::path::PathBuf;
+use anyhow::Context;
+
+const TOKEN_VAR: &str = "SGBF_TOKEN";
+
+fn path() -> anyhow::Result<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .context("neither XDG_CONFIG_HOME nor HOME is set")?;
+    Ok(config.join("sgbf").join("session"))
+}
+
+pub fn load() -> anyhow::Result<String> {
+    if let Ok(token) = std::env::var(TOKEN_VAR) {
+        return Ok(token);
+    }
+    let path = path()?;
+    let token = std::fs::read_to_string(&path)
+        .with_context(|| format!("no session in {}, run `sgbf login` first", path.display()))?;
+    Ok(token.trim().to_string())
+}
+
+/// Saves the token, readable only by the current user. Returns where it was saved.
+pub fn store(token: &str) -> anyhow::Result<PathBuf> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(&path).with_context(|| format!("failed to write {}", path.display()))?;
+    writeln!(file, "{}", token)?;
+    Ok(path)
+}
@@ -1,12 +1,14 @@
-
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 use sgbf_client::parsing::Parser;
+use sgbf_client::source::FixtureSource;
 
 pub fn criterion_benchmark(c: &mut Criterion) {
     let parser = Parser::default();
-    let document = include_str!("../tests/data/calendar.html");
-    c.bench_function("bench parse_calendar", |b| b.iter(|| parser.parse_calendar(black_box(String::from(document)))));
+    let document = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data"))
+        .page("calendar.html")
+        .expect("tests/data/calendar.html is missing");
+    c.bench_function("bench parse_calendar", |b| b.iter(|| parser.parse_calendar(black_box(document.clone()))));
 }
 
 criterion_group!(benches, criterion_benchmark);
-criterion_main!(benches);
\ No newline at end of file
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+//! Per-request clients against a local stand-in for upstream, with a fresh connection pool per
+//! client as before and with the shared pool.
+
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use sgbf_client::client::ClientBuilder;
+use sgbf_client::limiter::{RateLimitConfig, RateLimiter};
+use support::{StandIn, MENU};
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    let upstream = runtime.block_on(StandIn::start(MENU));
+    let builder = ClientBuilder::new()
+        .base_url(upstream.url())
+        .rate_limiter(RateLimiter::new(RateLimitConfig { requests_per_second: 0.0, max_concurrent: 64 }));
+
+    c.bench_function("request with a pool per client", |b| b.iter(|| runtime.block_on(async {
+        let http = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let client = builder.clone().http_client(http).with_token("token").unwrap();
+        client.get_user().await.unwrap()
+    })));
+
+    let pooled = builder.clone().pool().unwrap();
+    c.bench_function("request with a shared pool", |b| b.iter(|| runtime.block_on(async {
+        let client = pooled.clone().with_token("token").unwrap();
+        client.get_user().await.unwrap()
+    })));
+    eprintln!("stand-in accepted {} connections", upstream.connections());
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
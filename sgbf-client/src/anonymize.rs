@@ -0,0 +1,349 @@
+//! Replaces personal data in captured upstream pages with deterministic fake values, so they can
+//! be committed as test fixtures.
+//!
+//! Names, addresses, phone numbers and emails are found with the regular parsers and replaced
+//! wherever they occur in the page, including attributes such as `mailto:` links. Names are
+//! replaced word by word, so reordered and abbreviated names and names mentioned in notes and
+//! remarks are caught as well. The mapping is kept across pages, so a member renamed in
+//! `members.html` gets the same fake name in `calendar.html` and `reservations.html` when both
+//! are run through the same [`Anonymizer`].
+
+use std::collections::{HashMap, HashSet};
+use regex::{Captures, Regex};
+use crate::hash::fnv1a;
+use crate::parsing::{PageKind, ParseError, Parser};
+
+const FIRST_NAMES: &[&str] = &[
+    "Anna", "Beat", "Claudia", "Daniel", "Eva", "Fritz", "Gabi", "Hans", "Irene", "Jonas",
+    "Karin", "Lukas", "Monika", "Nico", "Olivia", "Peter", "Regula", "Stefan", "Tanja", "Urs",
+];
+const LAST_NAMES: &[&str] = &[
+    "Ammann", "Brunner", "Caduff", "Dubach", "Egli", "Frei", "Gerber", "Huber", "Imhof", "Jost",
+    "Keller", "Lanz", "Meier", "Nef", "Oswald", "Pfister", "Rüegg", "Schmid", "Tobler", "Vogt",
+];
+const STREETS: &[&str] = &["Dorfstrasse", "Bahnhofstrasse", "Hauptstrasse", "Kirchweg", "Seestrasse", "Flugplatzweg"];
+const CITIES: &[&str] = &["Fliegerwil", "Beispielhausen", "Testdorf", "Fiktivikon", "Platzhalden"];
+
+// phone numbers start with a prefix and may be grouped with spaces, slashes or dashes, but never
+// with dots or colons so that dates and times are left alone
+const PHONE_PATTERN: &str = r"(?:\+|\b0)\d[\d /-]{6,}\d";
+const EMAIL_PATTERN: &str = r"[\w.+-]+@[\w-]+(?:\.[\w-]+)+";
+// separators within a name, e.g. "Muster-Meier, H."
+const NAME_SEPARATORS: &[char] = &[' ', ',', '-', '.', '/', '(', ')'];
+
+#[derive(Debug)]
+pub struct Anonymizer {
+    parser: Parser,
+    phone: Regex,
+    email: Regex,
+    /// words of names and their fakes, also in upper case
+    words: HashMap<String, String>,
+    used_words: HashSet<String>,
+    replacements: HashMap<String, String>,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self {
+            parser: Parser::default(),
+            phone: Regex::new(PHONE_PATTERN).unwrap(),
+            email: Regex::new(EMAIL_PATTERN).unwrap(),
+            words: HashMap::new(),
+            used_words: HashSet::new(),
+            replacements: HashMap::new(),
+        }
+    }
+}
+
+/// Personal data the parsers found on a page.
+#[derive(Debug, Default)]
+struct Found {
+    names: Vec<String>,
+    address_lines: Vec<String>,
+}
+
+impl Anonymizer {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns the page with all personal data replaced. Fails if the page can't be parsed, since
+    /// the personal data could then not be located reliably, or if it can't be parsed anymore
+    /// after replacing it.
+    pub fn anonymize(&mut self, page: PageKind, html: &str) -> Result<String, ParseError> {
+        Ok(self.anonymize_all(&[(page, html)])?.remove(0))
+    }
+
+    /// Like [`Anonymizer::anonymize`] for several pages, with the names on all of them known
+    /// before any fake is picked, so that no fake is a real name from another page.
+    pub fn anonymize_all(&mut self, pages: &[(PageKind, &str)]) -> Result<Vec<String>, ParseError> {
+        // serialize through scraper first so the text matches what the parsers see
+        let pages = pages.iter()
+            .map(|(page, html)| (*page, scraper::Html::parse_document(html).html()))
+            .collect::<Vec<_>>();
+        let mut names = vec![];
+        for (page, html) in &pages {
+            let found = self.find(*page, html)?;
+            names.extend(found.names);
+            for line in &found.address_lines {
+                self.address_line(line);
+            }
+        }
+        self.names(&names);
+        pages.into_iter()
+            .map(|(page, html)| {
+                let html = self.replace(html);
+                self.find(page, &html)?;
+                Ok(html)
+            })
+            .collect()
+    }
+
+    fn find(&self, page: PageKind, html: &str) -> Result<Found, ParseError> {
+        let mut found = Found::default();
+        match page {
+            PageKind::Members => {
+                for member in self.parser.parse_members(html.to_string())? {
+                    found.names.push(member.name);
+                    if let Some(address) = &member.address {
+                        found.address_lines.extend(address.street.iter()
+                            .flat_map(|street| street.split(", "))
+                            .map(str::to_string));
+                        if let (Some(postcode), Some(city)) = (&address.postcode, &address.city) {
+                            found.address_lines.push(format!("{} {}", postcode, city));
+                        }
+                    }
+                }
+            }
+            PageKind::Calendar => {
+                found.names.extend(self.parser.parse_calendar(html.to_string())?.into_iter()
+                    .flat_map(|day| day.entries)
+                    .map(|entry| entry.name));
+            }
+            PageKind::Reservations => {
+                found.names.extend(self.parser.parse_reservations(html.to_string())?.into_iter()
+                    .map(|reservation| reservation.reserved_by));
+            }
+            PageKind::Day => {
+                found.names.extend(self.parser.parse_day(html.to_string())?.entries.into_iter()
+                    .map(|entry| entry.name));
+            }
+            PageKind::Duty => {
+                self.parser.parse_duty(html.to_string())?;
+                // the duty form is the sign-up page of another participant type, so it lists
+                // who else is on duty that day like the day page does
+                if let Ok(day) = self.parser.parse_day(html.to_string()) {
+                    found.names.extend(day.entries.into_iter().map(|entry| entry.name));
+                }
+            }
+            PageKind::Menu => {
+                found.names.push(self.parser.parse_menu(html.to_string())?);
+            }
+        }
+        Ok(found)
+    }
+
+    fn replace(&mut self, html: String) -> String {
+        let phones = self.phone.find_iter(&html).map(|m| m.as_str().to_string()).collect::<Vec<_>>();
+        for phone in phones {
+            self.phone_number(&phone);
+        }
+        let emails = self.email.find_iter(&html).map(|m| m.as_str().to_string()).collect::<Vec<_>>();
+        for email in emails {
+            self.email_address(&email);
+        }
+        // longest first, so that a value is not partially replaced by a shorter one it contains
+        let mut replacements = self.replacements.iter().collect::<Vec<_>>();
+        replacements.sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let html = replacements.into_iter()
+            .fold(html, |html, (original, fake)| html.replace(original.as_str(), fake));
+
+        // whole words only, in one pass so that fakes are never replaced again
+        if self.words.is_empty() {
+            return html;
+        }
+        let mut words = self.words.keys().collect::<Vec<_>>();
+        words.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+        let pattern = words.iter().map(|word| regex::escape(word)).collect::<Vec<_>>().join("|");
+        let names = Regex::new(&format!(r"\b(?:{})\b", pattern)).expect("escaped words form a valid pattern");
+        names.replace_all(&html, |captures: &Captures| self.words[&captures[0]].clone()).into_owned()
+    }
+
+    /// Assigns a fake to every word of the names. Words are mapped one by one, so "Muster Hans",
+    /// "Hans Muster" and "H. Muster" stay consistent.
+    fn names(&mut self, names: &[String]) {
+        let new_words = names.iter()
+            .flat_map(|name| name.split(NAME_SEPARATORS))
+            .filter(|word| is_name_word(word) && !self.words.contains_key(*word))
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        // a fake must not be a name that is replaced itself
+        self.used_words.extend(new_words.iter().cloned());
+        for word in new_words {
+            if self.words.contains_key(&word) {
+                continue;
+            }
+            let fake = self.fake_word(&word);
+            let upper = word.to_uppercase();
+            if upper != word {
+                self.words.insert(upper, fake.to_uppercase());
+            }
+            self.words.insert(word, fake);
+        }
+    }
+
+    fn fake_word(&mut self, word: &str) -> String {
+        let pool = FIRST_NAMES.iter().chain(LAST_NAMES).collect::<Vec<_>>();
+        let start = fnv1a(word.as_bytes()) as usize % pool.len();
+        let fake = (0..)
+            .map(|n| {
+                let n = start + n;
+                match n / pool.len() {
+                    0 => pool[n % pool.len()].to_string(),
+                    round => format!("{}{}", pool[n % pool.len()], round),
+                }
+            })
+            .find(|fake| !self.used_words.contains(fake))
+            .unwrap_or_default();
+        self.used_words.insert(fake.clone());
+        fake
+    }
+
+    fn address_line(&mut self, line: &str) {
+        let line = line.trim();
+        if !is_replaceable(line) || self.replacements.contains_key(line) {
+            return;
+        }
+        let hash = fnv1a(line.as_bytes()) as usize;
+        // "8000 Zürich" keeps the postcode/city shape, anything else becomes a street
+        let fake = match line.split_once(' ') {
+            Some((postcode, _)) if postcode.len() == 4 && postcode.chars().all(|c| c.is_ascii_digit()) => {
+                format!("{} {}", 1000 + hash % 9000, CITIES[hash % CITIES.len()])
+            }
+            _ => format!("{} {}", STREETS[hash % STREETS.len()], 1 + hash % 99),
+        };
+        self.replacements.insert(line.to_string(), fake);
+    }
+
+    fn phone_number(&mut self, phone: &str) {
+        if self.replacements.contains_key(phone) {
+            return;
+        }
+        // keep the prefix and the grouping, scramble the remaining digits
+        let mut digits = fnv1a(phone.as_bytes());
+        let fake = phone.char_indices()
+            .map(|(i, c)| {
+                if i < 3 || !c.is_ascii_digit() {
+                    return c;
+                }
+                let digit = (digits % 10) as u32;
+                digits = (digits / 10).wrapping_add(digits.rotate_left(7));
+                char::from_digit(digit, 10).unwrap_or(c)
+            })
+            .collect();
+        self.replacements.insert(phone.to_string(), fake);
+    }
+
+    fn email_address(&mut self, email: &str) {
+        if self.replacements.contains_key(email) {
+            return;
+        }
+        let fake = format!("person{:08x}@example.com", fnv1a(email.as_bytes()) as u32);
+        self.replacements.insert(email.to_string(), fake);
+    }
+}
+
+// names are capitalized, which keeps particles such as "von" and all of the markup untouched.
+// single letters are initials.
+fn is_name_word(word: &str) -> bool {
+    word.chars().count() >= 2 && word.chars().next().is_some_and(char::is_uppercase)
+}
+
+// very short values would match all over the markup
+fn is_replaceable(value: &str) -> bool {
+    value.chars().count() >= 3 && value.chars().any(char::is_alphanumeric)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MEMBERS: &str = "<table>\
+        <tr><th>Name</th><th>Adresse</th><th>Telefon</th><th>Fax</th><th>Mobil</th><th>E-Mail</th></tr>\
+        <tr><td>Muster Hans</td><td>Seeweg 12<br>8000 Zürich</td><td>044 123 45 67</td><td>044 123 45 68</td>\
+        <td>079 765 43 21</td><td><a href=\"mailto:hans@muster.ch\">hans@muster.ch</a></td></tr>\
+        </table>";
+
+    #[test]
+    fn test_anonymize_members() {
+        let mut anonymizer = Anonymizer::new();
+        let html = anonymizer.anonymize(PageKind::Members, MEMBERS).unwrap();
+        for original in ["Muster", "Hans", "Seeweg", "Zürich", "123 45 67", "123 45 68", "765 43 21", "hans@muster.ch"] {
+            assert!(!html.contains(original), "{} was not replaced", original);
+        }
+        let members = Parser::default().parse_members(html.clone()).unwrap();
+        assert_eq!(members.len(), 1);
+        assert!(members[0].private.email.as_deref().unwrap().ends_with("@example.com"));
+        // deterministic
+        assert_eq!(Anonymizer::new().anonymize(PageKind::Members, MEMBERS).unwrap(), html);
+    }
+
+    #[test]
+    fn test_names_are_consistent() {
+        let mut anonymizer = Anonymizer::new();
+        anonymizer.names(&["Muster Hans".to_string(), "Hans Muster".to_string(), "von Allmen-Muster".to_string()]);
+        assert_ne!(anonymizer.words["Hans"], anonymizer.words["Muster"]);
+        assert_eq!(anonymizer.words["MUSTER"], anonymizer.words["Muster"].to_uppercase());
+        assert!(anonymizer.words.contains_key("Allmen"));
+        assert!(!anonymizer.words.contains_key("von"));
+    }
+
+    const CALENDAR: &str = "<table>\
+        <tr><th>Datum</th><th>KW</th><th>SF</th><th>Feiertag</th><th>Info</th><th>Zeit</th><th>Funktion</th>\
+        <th>Bemerkung</th><th>Name</th></tr>\
+        <tr><td><a name=\"top\"></a><a name=\"2023-06-04\">So 04.06.</a></td><td>22</td><td>3 (1)</td>\
+        <td>Fluglager mit Hans Muster</td><td>MUSTER fliegt nur vormittags</td><td>09:00 - 17:00</td><td>FI</td>\
+        <td><a title=\"Vertretung H. Muster, sonst Anna Keller\">i</a></td><td>Muster Hans</td></tr>\
+        <tr><td><a name=\"top\"></a><a name=\"2023-06-04\">So 04.06.</a></td><td>22</td><td>3 (1)</td>\
+        <td></td><td></td><td>09:00 - 13:00</td><td>S</td><td></td><td>Keller-Brunner Anna</td></tr>\
+        </table>";
+
+    const RESERVATIONS: &str = "<table>\
+        <tr><td><table><tr><td><a name=\"12\"></a>HB-3187 ASK21</td><td>01.06.2023</td><td>Keller Anna</td></tr></table></td></tr>\
+        <tr><td>04.06.2023 10:00 - 12:00</td></tr>\
+        <tr><td>Übergabe an Muster</td></tr>\
+        </table>";
+
+    #[test]
+    fn test_no_original_name_survives() {
+        let pages = [(PageKind::Members, MEMBERS), (PageKind::Calendar, CALENDAR), (PageKind::Reservations, RESERVATIONS)];
+        let [members, calendar, reservations] = <[String; 3]>::try_from(Anonymizer::new().anonymize_all(&pages).unwrap()).unwrap();
+        for html in [&members, &calendar, &reservations] {
+            for original in ["Muster", "MUSTER", "Hans", "Anna", "Keller", "Brunner"] {
+                assert!(!html.contains(original), "{} survived in {}", original, html);
+            }
+        }
+        let days = Parser::default().parse_calendar(calendar).unwrap();
+        assert_eq!(days[0].entries.len(), 2);
+        // the same person gets the same fake on every page
+        let reserved_by = &Parser::default().parse_reservations(reservations).unwrap()[0].reserved_by;
+        assert!(days[0].entries[1].name.ends_with(reserved_by.split(' ').nth(1).unwrap()));
+    }
+
+    #[test]
+    fn test_fixtures_survive_anonymization() {
+        let read = |name: &str| std::fs::read_to_string(format!("{}/tests/data/{}", env!("CARGO_MANIFEST_DIR"), name)).unwrap();
+        let (members, calendar, reservations) = (read("members.html"), read("calendar.html"), read("reservations.html"));
+        let pages = [(PageKind::Members, members.as_str()), (PageKind::Calendar, calendar.as_str()), (PageKind::Reservations, reservations.as_str())];
+        let [anon_members, anon_calendar, anon_reservations] = <[String; 3]>::try_from(Anonymizer::new().anonymize_all(&pages).unwrap()).unwrap();
+
+        let parser = Parser::default();
+        assert_eq!(parser.parse_members(anon_members).unwrap().len(), parser.parse_members(members).unwrap().len());
+        let days = parser.parse_calendar(calendar).unwrap();
+        let anon_days = parser.parse_calendar(anon_calendar).unwrap();
+        assert_eq!(anon_days.len(), days.len());
+        assert!(anon_days.iter().zip(&days).all(|(anon, day)| anon.entries.len() == day.entries.len()));
+        assert_eq!(parser.parse_reservations(anon_reservations.clone()).unwrap().len(), parser.parse_reservations(reservations.clone()).unwrap().len());
+        assert_eq!(parser.parse_fleet(anon_reservations).unwrap().len(), parser.parse_fleet(reservations).unwrap().len());
+    }
+}
@@ -0,0 +1,58 @@
+//! Anonymizes captured upstream pages for use as test fixtures.
+//!
+//! Usage: `anonymize <output dir> <page.html>...`, where each file is named after the page it
+//! holds (`members.html`, `calendar.html`, `reservations.html`, ...), optionally followed by a
+//! `-` suffix (`day-2024-05-01.html`, `duty-2024-05-01-3.html`). Output files keep their names.
+
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context};
+use sgbf_client::anonymize::Anonymizer;
+use sgbf_client::parsing::PageKind;
+
+const PAGES: &[PageKind] = &[
+    // members first, they hold most of the names used on the other pages
+    PageKind::Members,
+    PageKind::Calendar,
+    PageKind::Reservations,
+    PageKind::Day,
+    PageKind::Duty,
+    PageKind::Menu,
+];
+
+fn page_kind(path: &Path) -> Option<PageKind> {
+    let stem = path.file_stem()?.to_str()?;
+    let name = stem.split('-').next()?;
+    PAGES.iter().copied().find(|page| page.to_string() == name)
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut args = std::env::args_os().skip(1);
+    let Some(output) = args.next().map(PathBuf::from) else {
+        bail!("usage: anonymize <output dir> <page.html>...");
+    };
+    let mut files = args.map(PathBuf::from)
+        .map(|path| match page_kind(&path) {
+            Some(page) => Ok((page, path)),
+            None => bail!("unknown page for {}", path.display()),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    files.sort_by_key(|(page, _)| PAGES.iter().position(|p| p == page));
+
+    std::fs::create_dir_all(&output)?;
+    let pages = files.iter()
+        .map(|(page, path)| {
+            let html = std::fs::read_to_string(path)
+                .with_context(|| format!("could not read {}", path.display()))?;
+            Ok((*page, html))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let pages = pages.iter().map(|(page, html)| (*page, html.as_str())).collect::<Vec<_>>();
+    let anonymized = Anonymizer::new().anonymize_all(&pages).context("could not anonymize pages")?;
+    for ((_, path), html) in files.iter().zip(anonymized) {
+        let target = output.join(path.file_name().unwrap_or_default());
+        std::fs::write(&target, html)
+            .with_context(|| format!("could not write {}", target.display()))?;
+        println!("{} -> {}", path.display(), target.display());
+    }
+    Ok(())
+}
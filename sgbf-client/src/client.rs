@@ -1,66 +1,428 @@
-use std::sync::Arc;
-use anyhow::Context;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use futures_util::StreamExt;
 use reqwest::cookie;
 use reqwest::cookie::CookieStore;
+use reqwest::header::{COOKIE, USER_AGENT};
 use serde::{Serialize};
 use thiserror::Error;
-use tracing::instrument;
+use tracing::{info, instrument, warn};
 use crate::parsing;
-use crate::model::{Day, DayOverview, EditAction, Member, ParticipantType, Reservation, RosterEntryType};
-use crate::parsing::Parser;
+use crate::model::{Day, DayOverview, Duty, EditAction, Member, ParticipantType, Reservation, ReservationRequest, RosterEntryType};
+use crate::model::aircraft::FleetAircraft;
+use crate::model::query::{CalendarQuery, ReservationQuery};
+use crate::parsing::{PageKind, PageState, ParseError, Parser};
+use crate::limiter::{RateLimiter, RetryPolicy};
+use crate::transport::{RawResponse, RecordedRequest, Transport};
 
 pub struct Client {
     inner: reqwest::Client,
+    base_url: reqwest::Url,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
     cookie_provider: Arc<cookie::Jar>,
+    credentials: Option<Credentials>,
+    transport: Transport,
+    limiter: RateLimiter,
+    retry: RetryPolicy,
+    concurrency: usize,
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url.as_str())
+            .field("relogin", &self.credentials.is_some())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+struct Credentials {
+    username: String,
+    password: String,
 }
 
 pub type Result<T> = std::result::Result<T, ClientError>;
 
+/// Whether a request only reads upstream state. Some pages are read with a `POST` of their
+/// filter form, so the method doesn't tell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Read,
+    Write,
+}
+
 #[derive(Debug, Error)]
 pub enum ClientError {
-    #[error("invalid token")]
-    InvalidToken,
-    #[error("unknown error")]
-    Unknown(#[from] anyhow::Error)
+    #[error("invalid client configuration: {0}")]
+    Configuration(String),
+    #[error("failed to reach upstream")]
+    Network(#[from] reqwest::Error),
+    #[error("upstream responded with status {0}")]
+    Status(reqwest::StatusCode),
+    #[error("session expired")]
+    SessionExpired,
+    #[error("upstream is in maintenance")]
+    Maintenance,
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error("upstream rejected write: {0}")]
+    WriteRejected(String),
+    /// The request was refused before sending it to upstream.
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("no recorded response for {0}")]
+    Unrecorded(String),
 }
 
-impl Client {
-    #[instrument(skip(password))]
-    pub async fn from_credentials(username: &str, password: &str) -> anyhow::Result<Self> {
-        let provider = Arc::new(cookie::Jar::default());
-        let client = Self {
-            inner: reqwest::Client::builder().cookie_provider(provider.clone()).build().unwrap(),
-            cookie_provider: provider,
+/// The outcome of [`Client::get_days`]. A day that failed doesn't keep the others from loading.
+#[derive(Debug, Default)]
+pub struct DayBatch {
+    pub days: BTreeMap<chrono::NaiveDate, Day>,
+    pub failures: Vec<(chrono::NaiveDate, ClientError)>,
+}
+
+impl DayBatch {
+    pub fn is_complete(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+impl ClientError {
+    /// Whether repeating the same request may succeed.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ClientError::Network(error) => error.is_timeout() || error.is_connect(),
+            ClientError::Status(status) => status.is_server_error() || *status == reqwest::StatusCode::TOO_MANY_REQUESTS,
+            _ => false,
+        }
+    }
+}
+
+/// Configures and creates a [`Client`].
+///
+/// Clients only own their session cookie. Connections are pooled in a `reqwest::Client` that is
+/// shared by every client created from the same builder, or by all clients in the process if the
+/// builder doesn't set up a pool of its own, so creating a client per request is cheap and reuses
+/// open connections to upstream. Blocking clients are the exception, see `client::blocking`.
+///
+/// A pool passed with [`ClientBuilder::http_client`] should be built without a cookie store and
+/// with `redirect::Policy::none()`, since the session cookie is managed by [`Client`] itself.
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    base_url: String,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    http_client: Option<reqwest::Client>,
+    relogin: bool,
+    transport: Transport,
+    limiter: RateLimiter,
+    retry: RetryPolicy,
+    concurrency: usize,
+}
+
+impl Default for ClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            http_client: None,
+            relogin: false,
+            transport: Transport::Network,
+            limiter: RateLimiter::shared(),
+            retry: RetryPolicy::default(),
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+}
+
+impl ClientBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Base url of the upstream site, e.g. a staging mirror or a local mock server.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Total timeout for each upstream request.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Connect timeout. Gives the builder a connection pool of its own, see [`ClientBuilder::pool`].
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Use a shared `reqwest::Client` instead of building a new one.
+    pub fn http_client(mut self, client: reqwest::Client) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Keep the credentials passed to [`ClientBuilder::login`] and log in again once when the
+    /// session expires. Clients without credentials fail with [`ClientError::SessionExpired`].
+    pub fn relogin(mut self, relogin: bool) -> Self {
+        self.relogin = relogin;
+        self
+    }
+
+    /// Records upstream exchanges to or replays them from a directory, see [`crate::transport`].
+    /// Defaults to [`Transport::Network`].
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    /// Limits the requests sent to upstream. Defaults to [`RateLimiter::shared`], so all clients
+    /// in the process share one budget unless given their own.
+    pub fn rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// How reads that failed for a transient reason are retried. Logins and writes are never
+    /// retried.
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// How many requests bulk calls such as [`Client::get_days`] send at once. The rate limiter
+    /// still applies on top of this.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets up the connection pool now, so that all clones of this builder share it. Only needed
+    /// with settings that require a pool of its own, such as [`ClientBuilder::connect_timeout`].
+    pub fn pool(mut self) -> Result<Self> {
+        self.http_client = Some(self.connection_pool()?);
+        Ok(self)
+    }
+
+    /// Replaces the pool with one only the clients of this builder use. Pooled connections are
+    /// driven by the runtime that opened them, so clients on different runtimes can't share one.
+    #[cfg(feature = "blocking")]
+    fn private_pool(mut self) -> Result<Self> {
+        let mut builder = http_client_builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        self.http_client = Some(builder.build()?);
+        Ok(self)
+    }
+
+    fn connection_pool(&self) -> Result<reqwest::Client> {
+        if let Some(client) = &self.http_client {
+            return Ok(client.clone());
+        }
+        let Some(timeout) = self.connect_timeout else {
+            return Ok(shared_http_client().clone());
         };
-        client.login(username, password).await.context("failed to login")?;
+        Ok(http_client_builder().connect_timeout(timeout).build()?)
+    }
+
+    /// Creates a client without a session.
+    pub fn build(self) -> Result<Client> {
+        let base_url = reqwest::Url::parse(self.base_url.trim_end_matches('/'))
+            .map_err(|e| ClientError::Configuration(format!("invalid base url: {}", e)))?;
+        let inner = self.connection_pool()?;
+        Ok(Client {
+            inner,
+            base_url,
+            timeout: self.timeout,
+            user_agent: self.user_agent,
+            cookie_provider: Arc::new(cookie::Jar::default()),
+            credentials: None,
+            transport: self.transport,
+            limiter: self.limiter,
+            retry: self.retry,
+            concurrency: self.concurrency,
+        })
+    }
+
+    /// Creates a client and logs in with the given credentials.
+    #[instrument(skip(self, password))]
+    pub async fn login(self, username: &str, password: &str) -> Result<Client> {
+        let relogin = self.relogin;
+        let mut client = self.build()?;
+        client.login(username, password).await?;
+        if relogin {
+            client.credentials = Some(Credentials {
+                username: username.to_string(),
+                password: password.to_string(),
+            });
+        }
         Ok(client)
     }
 
+    /// Creates a client reusing an existing session token.
+    #[instrument(skip(self, token))]
+    pub fn with_token(self, token: &str) -> Result<Client> {
+        let client = self.build()?;
+        client.cookie_provider.add_cookie_str(format!("{}={}", SESSION_COOKIE, token).as_str(), &client.base_url);
+        Ok(client)
+    }
+}
+
+impl Client {
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::new()
+    }
+
+    #[instrument(skip(password))]
+    pub async fn from_credentials(username: &str, password: &str) -> Result<Self> {
+        ClientBuilder::new().login(username, password).await
+    }
+
     #[instrument(skip(token))]
-    pub async fn from_token(token: &str) -> Self {
-        let jar = cookie::Jar::default();
-        jar.add_cookie_str(format!("PHPSESSID={}", token).as_str(), &reqwest::Url::parse(BASE_URL).unwrap());
-        let provider = Arc::new(jar);
+    pub async fn from_token(token: &str) -> Result<Self> {
+        ClientBuilder::new().with_token(token)
+    }
+
+    pub fn base_url(&self) -> &reqwest::Url {
+        &self.base_url
+    }
+
+    pub fn get_token(&self) -> Result<String> {
+        let jar = self.cookie_provider.as_ref();
+        let cookies = jar.cookies(&self.base_url).ok_or(ClientError::SessionExpired)?;
+        let cookies = cookies.to_str().map_err(|_| ClientError::SessionExpired)?;
+        cookies.split(';')
+            .filter_map(|cookie| cookie.trim().split_once('='))
+            .find(|(name, _)| *name == SESSION_COOKIE)
+            .map(|(_, value)| value.to_string())
+            .ok_or(ClientError::SessionExpired)
+    }
 
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url.as_str().trim_end_matches('/'), path)
+    }
 
-        Self {
-            inner: reqwest::Client::builder().cookie_provider(provider.clone()).build().unwrap(),
-            cookie_provider: provider,
+    /// Executes a request with the session cookie attached, following redirects manually so that
+    /// cookies set on intermediate responses end up in the session.
+    async fn send(&self, request: reqwest::Request) -> Result<RawResponse> {
+        let mut response = self.execute(request).await?;
+        for _ in 0..MAX_REDIRECTS {
+            if !response.status.is_redirection() {
+                break;
+            }
+            let location = response.location.as_ref()
+                .and_then(|location| response.url.join(location).ok());
+            let Some(location) = location else {
+                break;
+            };
+            let request = self.inner.get(location).build()?;
+            response = self.execute(request).await?;
         }
+        Ok(response)
     }
 
-    pub fn get_token(&self) -> String {
-        let jar = self.cookie_provider.as_ref();
-        let cookie = jar.cookies(&reqwest::Url::parse(BASE_URL).unwrap()).unwrap();
-        let cookie = cookie.to_str().unwrap();
-        let cookie = cookie.split(';').next().unwrap();
-        let cookie = cookie.split('=').nth(1).unwrap();
-        cookie.to_string()
+    async fn execute(&self, mut request: reqwest::Request) -> Result<RawResponse> {
+        if let Some(cookie) = self.cookie_provider.cookies(request.url()) {
+            request.headers_mut().insert(COOKIE, cookie);
+        }
+        if let Some(user_agent) = self.user_agent.as_ref().and_then(|ua| ua.parse().ok()) {
+            request.headers_mut().insert(USER_AGENT, user_agent);
+        }
+        if let Some(timeout) = self.timeout {
+            *request.timeout_mut() = Some(timeout);
+        }
+        let recorded = RecordedRequest::new(&request);
+        let response = match &self.transport {
+            Transport::Network => {
+                let _permit = self.limiter.acquire().await;
+                RawResponse::read(self.inner.execute(request).await?).await?
+            }
+            Transport::Record(recorder) => {
+                let _permit = self.limiter.acquire().await;
+                let response = RawResponse::read(self.inner.execute(request).await?).await?;
+                recorder.record(&recorded, &response);
+                response
+            }
+            Transport::Replay(replayer) => replayer.replay(&recorded, request.url())?,
+        };
+        let mut cookies = response.set_cookies.iter();
+        self.cookie_provider.set_cookies(&mut cookies, &response.url);
+        Ok(response)
+    }
+
+    /// Sends a request and returns the body of the resulting page, mapping upstream failures to
+    /// their [`ClientError`]. If the session expired and the client holds credentials, it logs in
+    /// again and retries the request once.
+    async fn fetch(&self, page: PageKind, operation: Operation, request: reqwest::Request) -> Result<String> {
+        let retry = self.credentials.as_ref().and_then(|_| request.try_clone());
+        match self.fetch_retrying(page, operation, request).await {
+            Err(ClientError::SessionExpired) => {
+                let (Some(credentials), Some(request)) = (&self.credentials, retry) else {
+                    return Err(ClientError::SessionExpired);
+                };
+                info!(%page, "session expired, logging in again");
+                self.login(&credentials.username, &credentials.password).await?;
+                self.fetch_retrying(page, operation, request).await
+            }
+            result => result,
+        }
+    }
+
+    /// Retries reads on transient failures according to the [`RetryPolicy`]. Writes may have
+    /// changed upstream state before failing and are sent exactly once.
+    async fn fetch_retrying(&self, page: PageKind, operation: Operation, mut request: reqwest::Request) -> Result<String> {
+        if operation == Operation::Write {
+            return self.fetch_once(page, request).await;
+        }
+        let mut attempt = 0;
+        loop {
+            let next = request.try_clone();
+            match (self.fetch_once(page, request).await, next) {
+                (Err(error), Some(next)) if error.is_transient() && attempt < self.retry.max_retries => {
+                    let backoff = self.retry.backoff(attempt);
+                    warn!(%page, %error, attempt, ?backoff, "transient upstream failure, retrying");
+                    tokio::time::sleep(backoff).await;
+                    request = next;
+                    attempt += 1;
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    async fn fetch_once(&self, page: PageKind, request: reqwest::Request) -> Result<String> {
+        let RawResponse { status, body, .. } = self.send(request).await?;
+        if status == reqwest::StatusCode::SERVICE_UNAVAILABLE {
+            return Err(ClientError::Maintenance);
+        }
+        if !status.is_success() {
+            return Err(ClientError::Status(status));
+        }
+        match parsing::page_state(page, &body) {
+            PageState::Content => Ok(body),
+            PageState::LoggedOut => Err(ClientError::SessionExpired),
+            PageState::Maintenance => Err(ClientError::Maintenance),
+        }
     }
 
     #[instrument(skip(self, password))]
-    async fn login(&self, username: &str, password: &str) -> anyhow::Result<()> {
-        let url = format!("{}{}", BASE_URL, PATH_LOGIN);
+    async fn login(&self, username: &str, password: &str) -> Result<()> {
+        let url = self.url(PATH_LOGIN);
         let body = LoginBody {
             username: username.to_string(),
             password: password.to_string(),
@@ -68,92 +430,119 @@ impl Client {
         };
         let request = self.inner.post(url)
             .form(&body)
-            .build()
-            .context("failed to build request")?;
-        let _ = self.inner.execute(request).await.context("failed to execute request")?;
+            .build()?;
+        let response = self.send(request).await?;
+        if !response.status.is_success() {
+            return Err(ClientError::Status(response.status));
+        }
         Ok(())
     }
 
     pub async fn get_user(&self) -> Result<Option<String>> {
-        let url = format!("{}{}", BASE_URL, PATH_MENU);
+        let url = self.url(PATH_MENU);
         let request = self.inner.get(url)
-            .build()
-            .context("Failed to build request")?;
-        let response = self.inner.execute(request).await.context("Failed to execute request")?;
-        let body = response.text().await.context("Failed to read response body")?;
+            .build()?;
+        let body = self.fetch(PageKind::Menu, Operation::Read, request).await?;
         if body.contains("logout") {
-            let username = parsing::Parser::default().parse_menu(body)?;
+            let username = Parser::default().parse_menu(body)?;
             return Ok(Some(username));
         }
         Ok(None)
     }
 
     #[instrument(skip(self))]
-    pub async fn get_calendar(&self) -> Result<Vec<DayOverview>> {
-        let url = format!("{}{}", BASE_URL, PATH_CALENDAR);
+    pub async fn get_calendar(&self, query: &CalendarQuery) -> Result<Vec<DayOverview>> {
+        let url = self.url(PATH_CALENDAR);
         let request = self.inner.post(url)
-            .form(&[("timebracket", "-9"), ("event_type", "0")])
-            .build()
-            .context("Failed to build request")?;
-        let response = self.inner.execute(request).await.context("Failed to execute request")?;
+            .form(&query.form())
+            .build()?;
         // body is html
-        let body = response.text().await.context("Failed to read response body")?;
-        if body.contains("Eintrag vorhanden") {
-            return Err(ClientError::InvalidToken);
-        }
+        let body = self.fetch(PageKind::Calendar, Operation::Read, request).await?;
         // parse
-        Parser::default().parse_calendar(body).map_err(|e| e.into())
+        Parser::default().parse_calendar(body)
+            .map_err(ClientError::from)
     }
 
     #[instrument(skip(self))]
-    pub async fn get_reservations(&self) -> Result<Vec<Reservation>> {
-        let url = format!("{}{}", BASE_URL, PATH_RESERVATIONS);
+    pub async fn get_reservations(&self, query: &ReservationQuery) -> Result<Vec<Reservation>> {
+        let url = self.url(PATH_RESERVATIONS);
         let request = self.inner.post(url)
-            // Dacft=all&Dtimeframe=-1
-            .form(&[("Dacft", "all"), ("Dtimeframe", "-1")])
-            .build()
-            .context("Failed to build request")?;
-        let response = self.inner.execute(request).await.context("Failed to execute request")?;
+            .form(&query.form())
+            .build()?;
         // body is html
-        let body = response.text().await.context("Failed to read response body")?;
+        let body = self.fetch(PageKind::Reservations, Operation::Read, request).await?;
         // parse
-        Parser::default().parse_reservations(body).map_err(|e| e.into())
+        Parser::default().parse_reservations(body)
+            .map_err(ClientError::from)
+    }
+
+    /// The aircraft offered on the reservation page.
+    #[instrument(skip(self))]
+    pub async fn get_fleet(&self) -> Result<Vec<FleetAircraft>> {
+        let url = self.url(PATH_RESERVATIONS);
+        let request = self.inner.get(url)
+            .build()?;
+        let body = self.fetch(PageKind::Reservations, Operation::Read, request).await?;
+        Parser::default().parse_fleet(body)
+            .map_err(ClientError::from)
     }
 
     #[instrument(skip(self))]
     pub async fn get_members(&self) -> Result<Vec<Member>> {
-        let url = format!("{}{}", BASE_URL, PATH_MEMBERS);
+        let url = self.url(PATH_MEMBERS);
         let request = self.inner.get(url)
             .query(&[("dselect", "a")])
-            .build()
-            .context("Failed to build request")?;
-        let response = self.inner.execute(request).await.context("Failed to execute request")?;
+            .build()?;
         // body is html
-        let body = response.text().await.context("Failed to read response body")?;
+        let body = self.fetch(PageKind::Members, Operation::Read, request).await?;
         // parse
-        Parser::default().parse_members(body).map_err(|e| e.into())
+        Parser::default().parse_members(body)
+            .map_err(ClientError::from)
     }
 
     #[instrument(skip(self))]
-    pub async fn get_day(&self, date: chrono::NaiveDate) -> anyhow::Result<Day> {
-        let url = format!("{}{}", BASE_URL, PATH_DAY);
+    pub async fn get_day(&self, date: chrono::NaiveDate) -> Result<Day> {
+        let url = self.url(PATH_DAY);
         let request = self.inner.get(url)
             // fe_t=participant_sf&select_date=2023-06-04&fe_f=text
-            .query(&[("fe_t", "participant_sf"), ("select_date", date.format("%Y-%m-%d").to_string().as_ref()), ("fe_f", "text")])
-            .build()
-            .unwrap();
-        let response = self.inner.execute(request).await.unwrap();
+            .query(&[("fe_t", ParticipantType::GliderPilot.code()), ("select_date", date.format("%Y-%m-%d").to_string().as_ref()), ("fe_f", "text")])
+            .build()?;
         // body is html
-        let body = response.text().await.unwrap();
+        let body = self.fetch(PageKind::Day, Operation::Read, request).await?;
         // parse
         Parser::default().parse_day(body)
+            .map_err(ClientError::from)
+    }
+
+    /// Fetches several days concurrently, at most [`ClientBuilder::concurrency`] at a time. Pass
+    /// e.g. `from.iter_days().take(14)` for the next two weeks.
+    #[instrument(skip(self, dates))]
+    pub async fn get_days(&self, dates: impl IntoIterator<Item = chrono::NaiveDate>) -> DayBatch {
+        let results = futures_util::stream::iter(dates)
+            .map(|date| async move { (date, self.get_day(date).await) })
+            .buffer_unordered(self.concurrency)
+            .collect::<Vec<_>>()
+            .await;
+        let mut batch = DayBatch::default();
+        for (date, result) in results {
+            match result {
+                Ok(day) => {
+                    batch.days.insert(date, day);
+                }
+                Err(error) => batch.failures.push((date, error)),
+            }
+        }
+        batch.failures.sort_by_key(|(date, _)| *date);
+        batch
     }
 
+    /// Updates the sign-up for the given day and returns the day as re-read from upstream.
     #[instrument(skip(self))]
-    pub async fn update_day(&self, date: chrono::NaiveDate, day: Day) {
-        let url = format!("{}{}", BASE_URL, PATH_DAY_UPDATE);
+    pub async fn update_day(&self, date: chrono::NaiveDate, day: Day) -> Result<Day> {
+        let url = self.url(PATH_DAY_UPDATE);
+        let requested = day.entry_type;
         let remarks = day.remarks.unwrap_or_default();
-        let date = date.format("%Y-%m-%d").to_string();
+        let date_str = date.format("%Y-%m-%d").to_string();
         let mut form: Vec<(&str, &str)> = vec![
             ("RB_status", match day.entry_type {
                 Some(t) => match t {
@@ -169,11 +558,9 @@ impl Client {
                 EditAction::Edit => "edit",
                 EditAction::Add => "add",
             }),
-            ("Tfe_t", match day.participant_type {
-                ParticipantType::GliderPilot => "participant_sf",
-            }),
+            ("Tfe_t", day.participant_type.code()),
             ("Tfe_f", day.format.as_str()),
-            ("T_My_Date", date.as_ref())
+            ("T_My_Date", date_str.as_ref())
         ];
         let id = day.id.unwrap_or_default();
         let id = id.to_string();
@@ -182,21 +569,180 @@ impl Client {
         }
         let request = self.inner.post(url)
             .form(&form)
-            .build()
-            .unwrap();
-        let _ = self.inner.execute(request).await.unwrap();
+            .build()?;
+        self.fetch(PageKind::Day, Operation::Write, request).await?;
+        // upstream answers with the same page whether or not the write went through
+        let day = self.get_day(date).await?;
+        if day.entry_type != requested {
+            return Err(ClientError::WriteRejected(format!("expected entry type {:?}, found {:?}", requested, day.entry_type)));
+        }
+        Ok(day)
+    }
+
+    /// Reads the duty slot of the current user for the given day and duty type.
+    #[instrument(skip(self))]
+    pub async fn get_duty(&self, date: chrono::NaiveDate, participant_type: ParticipantType) -> Result<Duty> {
+        let url = self.url(PATH_DAY);
+        let request = self.inner.get(url)
+            .query(&[("fe_t", participant_type.code()), ("select_date", date.format("%Y-%m-%d").to_string().as_ref()), ("fe_f", "text")])
+            .build()?;
+        let body = self.fetch(PageKind::Duty, Operation::Read, request).await?;
+        Parser::default().parse_duty(body)
+            .map_err(ClientError::from)
+    }
+
+    /// Adds or changes a duty slot and returns it as re-read from upstream.
+    #[instrument(skip(self))]
+    pub async fn update_duty(&self, date: chrono::NaiveDate, duty: Duty) -> Result<Duty> {
+        let time_frame = duty.time_frame
+            .ok_or_else(|| ClientError::InvalidRequest("a duty needs a time frame".to_string()))?;
+        let action = match duty.action {
+            EditAction::Edit => "edit",
+            EditAction::Add => "add",
+        };
+        let mut form = vec![
+            ("Tfe_time_from", time_frame.start().format("%H:%M").to_string()),
+            ("Tfe_time_to", time_frame.end().format("%H:%M").to_string()),
+            ("TAfe_fsn", duty.remarks.clone().unwrap_or_default()),
+        ];
+        form.extend(duty_form(action, date, &duty));
+        self.post_duty(form).await?;
+        let updated = self.get_duty(date, duty.participant_type).await?;
+        if updated.time_frame != duty.time_frame {
+            return Err(ClientError::WriteRejected(format!("expected duty time frame {:?}, found {:?}", duty.time_frame, updated.time_frame)));
+        }
+        Ok(updated)
+    }
+
+    /// Removes the duty slot of the current user for the given day and duty type.
+    #[instrument(skip(self))]
+    pub async fn remove_duty(&self, date: chrono::NaiveDate, participant_type: ParticipantType) -> Result<()> {
+        let duty = self.get_duty(date, participant_type).await?;
+        if duty.action == EditAction::Add {
+            // nothing to remove
+            return Ok(());
+        }
+        self.post_duty(duty_form("delete", date, &duty)).await?;
+        if self.get_duty(date, participant_type).await?.action != EditAction::Add {
+            return Err(ClientError::WriteRejected(format!("{:?} duty on {} still exists after removing it", participant_type, date)));
+        }
+        Ok(())
+    }
+
+    async fn post_duty(&self, form: Vec<(&'static str, String)>) -> Result<()> {
+        let url = self.url(PATH_DAY_UPDATE);
+        let request = self.inner.post(url)
+            .form(&form)
+            .build()?;
+        self.fetch(PageKind::Duty, Operation::Write, request).await?;
+        Ok(())
+    }
+
+    /// Reserves an aircraft and returns the reservation as listed upstream.
+    #[instrument(skip(self))]
+    pub async fn create_reservation(&self, reservation: &ReservationRequest) -> Result<Reservation> {
+        self.post_reservation(reservation_form("add", None, Some(reservation))).await?;
+        self.get_reservations(&ReservationQuery::for_aircraft(&reservation.plane)).await?
+            .into_iter()
+            .find(|r| r.plane.registration_number == reservation.plane.registration_number && r.period == reservation.period)
+            .ok_or_else(|| ClientError::WriteRejected(format!("no reservation for {} ({}) after creating it", reservation.plane, reservation.period)))
+    }
+
+    /// Changes the period or comment of an existing reservation.
+    #[instrument(skip(self))]
+    pub async fn update_reservation(&self, id: i32, reservation: &ReservationRequest) -> Result<Reservation> {
+        self.post_reservation(reservation_form("edit", Some(id), Some(reservation))).await?;
+        let updated = self.get_reservations(&ReservationQuery::for_aircraft(&reservation.plane)).await?
+            .into_iter()
+            .find(|r| r.id == id)
+            .ok_or_else(|| ClientError::WriteRejected(format!("reservation {} disappeared while updating it", id)))?;
+        if updated.period != reservation.period {
+            return Err(ClientError::WriteRejected(format!("expected reservation {} for {}, found {}", id, reservation.period, updated.period)));
+        }
+        Ok(updated)
+    }
+
+    #[instrument(skip(self))]
+    pub async fn cancel_reservation(&self, id: i32) -> Result<()> {
+        self.post_reservation(reservation_form("delete", Some(id), None)).await?;
+        if self.get_reservations(&ReservationQuery::default()).await?.iter().any(|r| r.id == id) {
+            return Err(ClientError::WriteRejected(format!("reservation {} still exists after cancelling it", id)));
+        }
+        Ok(())
+    }
+
+    async fn post_reservation(&self, form: Vec<(&'static str, String)>) -> Result<()> {
+        let url = self.url(PATH_RESERVATION_UPDATE);
+        let request = self.inner.post(url)
+            .form(&form)
+            .build()?;
+        self.fetch(PageKind::Reservations, Operation::Write, request).await?;
+        Ok(())
     }
 }
 
-const BASE_URL: &str = "https://schlepppiloten.ch";
+pub const BASE_URL: &str = "https://schlepppiloten.ch";
+pub(crate) const SESSION_COOKIE: &str = "PHPSESSID";
+const MAX_REDIRECTS: usize = 10;
+const DEFAULT_CONCURRENCY: usize = 4;
 const PATH_MENU: &str = "/menu.php";
 const PATH_LOGIN: &str = "/edit/login_check.php";
 const PATH_CALENDAR: &str = "/roster/list_roster_new.php";
 const PATH_DAY: &str = "/roster/participant_edit.php";
 const PATH_DAY_UPDATE: &str = "/roster/participant_update.php";
 const PATH_RESERVATIONS: &str = "/roster/reservation_aircraft.php";
+const PATH_RESERVATION_UPDATE: &str = "/roster/reservation_aircraft_update.php";
 const PATH_MEMBERS: &str = "/edit/member_list.php";
 
+fn http_client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+// the pool used by builders without one of their own
+fn shared_http_client() -> &'static reqwest::Client {
+    static SHARED: OnceLock<reqwest::Client> = OnceLock::new();
+    SHARED.get_or_init(|| http_client_builder().build().expect("default http client"))
+}
+
+// fields shared by all duty edit forms
+fn duty_form(action: &'static str, date: chrono::NaiveDate, duty: &Duty) -> Vec<(&'static str, String)> {
+    let mut form = vec![
+        ("Bfieldedit_send", "Speichern".to_string()),
+        ("T_My_Action", action.to_string()),
+        ("Tfe_t", duty.participant_type.code().to_string()),
+        ("Tfe_f", duty.format.clone()),
+        ("T_My_Date", date.format("%Y-%m-%d").to_string()),
+    ];
+    if let Some(id) = duty.id {
+        form.push(("Tfe_r", id.to_string()));
+    }
+    form
+}
+
+// fields of the reservation form, dates and times are entered separately
+fn reservation_form(action: &'static str, id: Option<i32>, reservation: Option<&ReservationRequest>) -> Vec<(&'static str, String)> {
+    let mut form = vec![
+        ("T_My_Action", action.to_string()),
+        ("Bres_send", "Speichern".to_string()),
+    ];
+    if let Some(id) = id {
+        form.push(("Tres_r", id.to_string()));
+    }
+    if let Some(reservation) = reservation {
+        let period = &reservation.period;
+        form.extend([
+            ("Dacft", reservation.plane.registration_number.clone()),
+            ("Tdate_from", period.from.format("%d.%m.%Y").to_string()),
+            ("Ttime_from", period.from.format("%H:%M").to_string()),
+            ("Tdate_to", period.to.format("%d.%m.%Y").to_string()),
+            ("Ttime_to", period.to.format("%H:%M").to_string()),
+            ("TAres_comment", reservation.comment.clone().unwrap_or_default()),
+        ]);
+    }
+    form
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct LoginBody {
     #[serde(rename = "login_user")]
@@ -206,6 +752,9 @@ struct LoginBody {
     save: String,
 }
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+
 #[cfg(feature = "axum")]
 pub mod axum {
     use axum::extract::{FromRef, FromRequestParts, State};
@@ -224,16 +773,20 @@ pub mod axum {
     #[async_trait]
     impl <S> FromRequestParts<S> for super::Client
         where AuthCache: FromRef<S>,
+              super::ClientBuilder: FromRef<S>,
               S: Send + Sync
     {
         type Rejection = StatusCode;
-        async fn from_request_parts(parts: &mut Parts, _s: &S) -> Result<Self, Self::Rejection> {
+        async fn from_request_parts(parts: &mut Parts, s: &S) -> Result<Self, Self::Rejection> {
             // get AuthState from extensions
             let auth_state = parts.extensions.get::<AuthState>();
             if let Some(auth_state) = auth_state {
                 if let Some((token, username)) = &auth_state.0 {
                     debug!("user {}", username);
-                    return Ok(super::Client::from_token(token).await)
+                    // clients are created per request, the builder shares the pool and limiter
+                    return super::ClientBuilder::from_ref(s)
+                        .with_token(token)
+                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
                 }
             }
             Err(StatusCode::UNAUTHORIZED)
@@ -242,7 +795,9 @@ pub mod axum {
 
     #[derive(Debug, Default, Clone)]
     pub struct AuthCache {
-        pub tokens: Arc<Mutex<HashMap<String, (Instant, Option<String>)>>>
+        pub tokens: Arc<Mutex<HashMap<String, (Instant, Option<String>)>>>,
+        /// Only accept tokens added to the cache, never check unknown ones with upstream.
+        offline: bool,
     }
 
     impl AuthCache {
@@ -250,6 +805,11 @@ pub mod axum {
             Default::default()
         }
 
+        /// A cache for running without upstream, where tokens are issued locally.
+        pub fn offline() -> Self {
+            Self { offline: true, ..Default::default() }
+        }
+
         pub(crate) fn is_token_invalid(&self, token: &str) -> bool {
             let guard = self.tokens.lock().unwrap();
             let token = guard.get(token);
@@ -310,7 +870,8 @@ pub mod axum {
         mut req: http::Request<B>,
         next: axum::middleware::Next<B>
     ) -> Result<axum::response::Response, StatusCode>
-        where AuthCache: FromRef<S> {
+        where AuthCache: FromRef<S>,
+              super::ClientBuilder: FromRef<S> {
         let header = req.headers().get("Authorization");
         if let Some(header) = header {
             if let Ok(header) = header.to_str() {
@@ -327,11 +888,12 @@ pub mod axum {
                             .insert(AuthState(state.map(|s| (token.to_owned(), s))));
                         return Ok(next.run(req).await);
                     }
-                    if cache.is_token_invalid(&token) {
+                    if cache.is_token_invalid(&token) || cache.offline {
                         // return error
                         return Err(StatusCode::UNAUTHORIZED);
                     }
-                    let client = super::Client::from_token(&token).await;
+                    let client = super::ClientBuilder::from_ref(&s).with_token(&token)
+                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
                     let result = client.get_user().await;
                     if let Ok(result) = result {
                         cache.add_token(token.to_owned(), Duration::from_secs( 60 * 10), result.clone());
@@ -350,3 +912,111 @@ pub mod axum {
     }
 
 }
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+    use reqwest::cookie::CookieStore;
+    use reqwest::StatusCode;
+    use crate::limiter::RetryPolicy;
+    use crate::model::query::ReservationQuery;
+    use crate::transport::{RawResponse, RecordedRequest, Recorder, Transport};
+    use super::{ClientBuilder, ClientError};
+
+    const DAY: &str = "<html><body><form><table></table>\
+        <input type=\"hidden\"><input type=\"hidden\" value=\"add\"><input type=\"hidden\">\
+        <input type=\"hidden\"><input type=\"hidden\" value=\"text\"></form><table></table>\
+        <table><tr><td>Name (1)</td></tr><tr><td>Muster Hans</td><td></td><td>ab Mittag</td></tr></table>\
+        </body></html>";
+
+    #[test]
+    fn test_token_scoped_to_base_url() {
+        let client = ClientBuilder::new()
+            .base_url("http://localhost:8080/")
+            .with_token("abc123")
+            .unwrap();
+        assert_eq!(client.get_token().unwrap(), "abc123");
+        assert_eq!(client.url("/menu.php"), "http://localhost:8080/menu.php");
+        let other = reqwest::Url::parse("https://schlepppiloten.ch").unwrap();
+        assert!(client.cookie_provider.cookies(&other).is_none());
+    }
+
+    // records a failing and then a successful response for the request
+    fn record_flaky(recorder: &Recorder, request: reqwest::RequestBuilder) {
+        let request = request.build().unwrap();
+        let recorded = RecordedRequest::new(&request);
+        for status in [StatusCode::BAD_GATEWAY, StatusCode::OK] {
+            recorder.record(&recorded, &RawResponse {
+                status,
+                url: request.url().clone(),
+                location: None,
+                set_cookies: vec![],
+                body: "<html><body><table></table></body></html>".to_string(),
+            });
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_only_reads_are_retried() {
+        let dir = std::env::temp_dir().join(format!("sgbf-client-retry-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let http = reqwest::Client::new();
+        let recorder = Recorder::new(&dir);
+        record_flaky(&recorder, http.get("http://localhost:8080/menu.php"));
+        // the reservation list is read by posting its filter form
+        record_flaky(&recorder, http.post("http://localhost:8080/roster/reservation_aircraft.php").form(&ReservationQuery::default().form()));
+        record_flaky(&recorder, http.post("http://localhost:8080/roster/reservation_aircraft_update.php").form(&super::reservation_form("delete", Some(7), None)));
+
+        let client = ClientBuilder::new()
+            .base_url("http://localhost:8080")
+            .transport(Transport::replay(&dir))
+            .retry_policy(RetryPolicy { max_retries: 1, initial_backoff: Duration::from_millis(10), ..Default::default() })
+            .build()
+            .unwrap();
+        assert!(client.get_user().await.unwrap().is_none());
+        assert!(client.get_reservations(&ReservationQuery::default()).await.unwrap().is_empty());
+        let cancelled = client.cancel_reservation(7).await;
+        assert!(matches!(cancelled, Err(ClientError::Status(StatusCode::BAD_GATEWAY))));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_get_days_reports_failures() {
+        let dir = std::env::temp_dir().join(format!("sgbf-client-days-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let http = reqwest::Client::new();
+        let recorder = Recorder::new(&dir);
+        for (date, status) in [("2023-06-03", StatusCode::OK), ("2023-06-04", StatusCode::NOT_FOUND)] {
+            let request = http.get("http://localhost:8080/roster/participant_edit.php")
+                .query(&[("fe_t", "participant_sf"), ("select_date", date), ("fe_f", "text")])
+                .build()
+                .unwrap();
+            recorder.record(&RecordedRequest::new(&request), &RawResponse {
+                status,
+                url: request.url().clone(),
+                location: None,
+                set_cookies: vec![],
+                body: DAY.to_string(),
+            });
+        }
+
+        let client = ClientBuilder::new()
+            .base_url("http://localhost:8080")
+            .transport(Transport::replay(&dir))
+            .concurrency(2)
+            .build()
+            .unwrap();
+        let first = chrono::NaiveDate::from_ymd_opt(2023, 6, 3).unwrap();
+        let batch = client.get_days(first.iter_days().take(3)).await;
+        assert_eq!(batch.days.len(), 1);
+        assert_eq!(batch.days[&first].entries[0].name, "Muster Hans");
+        let failures = batch.failures.iter().map(|(date, error)| (date.to_string(), error)).collect::<Vec<_>>();
+        assert!(matches!(failures.as_slice(), [
+            (_, ClientError::Status(StatusCode::NOT_FOUND)),
+            (_, ClientError::Unrecorded(_)),
+        ]));
+        assert_eq!(failures[0].0, "2023-06-04");
+        assert!(!batch.is_complete());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,140 @@
+//! A synchronous wrapper around [`super::Client`] for scripts and cron jobs.
+//!
+//! Each client drives the async client on its own single-threaded tokio runtime, so it shares the
+//! parsers, model, rate limiter and connection handling with the async API. Like
+//! `reqwest::blocking`, it must not be created or used from within an async runtime.
+//!
+//! A runtime only drives its connections while one of its calls is blocking, so every client
+//! opens connections of its own instead of using the pool of the builder.
+
+use chrono::NaiveDate;
+use crate::model::{Day, DayOverview, Duty, Member, ParticipantType, Reservation, ReservationRequest};
+use crate::model::aircraft::FleetAircraft;
+use crate::model::query::{CalendarQuery, ReservationQuery};
+use super::{ClientBuilder, ClientError, DayBatch, Result};
+
+#[derive(Debug)]
+pub struct Client {
+    inner: super::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| ClientError::Configuration(format!("failed to start runtime: {}", e)))
+}
+
+impl Client {
+    pub fn from_credentials(username: &str, password: &str) -> Result<Self> {
+        Self::login(ClientBuilder::new(), username, password)
+    }
+
+    pub fn from_token(token: &str) -> Result<Self> {
+        Self::with_token(ClientBuilder::new(), token)
+    }
+
+    /// Creates a client from `builder` and logs in with the given credentials.
+    pub fn login(builder: ClientBuilder, username: &str, password: &str) -> Result<Self> {
+        let runtime = runtime()?;
+        let builder = {
+            let _runtime = runtime.enter();
+            builder.private_pool()?
+        };
+        let inner = runtime.block_on(builder.login(username, password))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Creates a client from `builder` reusing an existing session token.
+    pub fn with_token(builder: ClientBuilder, token: &str) -> Result<Self> {
+        let runtime = runtime()?;
+        let inner = {
+            let _runtime = runtime.enter();
+            builder.private_pool()?.with_token(token)?
+        };
+        Ok(Self { inner, runtime })
+    }
+
+    /// The async client, e.g. to hand it to code that is already async.
+    pub fn get_ref(&self) -> &super::Client {
+        &self.inner
+    }
+
+    pub fn get_token(&self) -> Result<String> {
+        self.inner.get_token()
+    }
+
+    pub fn get_user(&self) -> Result<Option<String>> {
+        self.runtime.block_on(self.inner.get_user())
+    }
+
+    pub fn get_calendar(&self, query: &CalendarQuery) -> Result<Vec<DayOverview>> {
+        self.runtime.block_on(self.inner.get_calendar(query))
+    }
+
+    pub fn get_reservations(&self, query: &ReservationQuery) -> Result<Vec<Reservation>> {
+        self.runtime.block_on(self.inner.get_reservations(query))
+    }
+
+    pub fn get_fleet(&self) -> Result<Vec<FleetAircraft>> {
+        self.runtime.block_on(self.inner.get_fleet())
+    }
+
+    pub fn get_members(&self) -> Result<Vec<Member>> {
+        self.runtime.block_on(self.inner.get_members())
+    }
+
+    pub fn get_day(&self, date: NaiveDate) -> Result<Day> {
+        self.runtime.block_on(self.inner.get_day(date))
+    }
+
+    pub fn get_days(&self, dates: impl IntoIterator<Item = NaiveDate>) -> DayBatch {
+        self.runtime.block_on(self.inner.get_days(dates))
+    }
+
+    pub fn update_day(&self, date: NaiveDate, day: Day) -> Result<Day> {
+        self.runtime.block_on(self.inner.update_day(date, day))
+    }
+
+    pub fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> Result<Duty> {
+        self.runtime.block_on(self.inner.get_duty(date, participant_type))
+    }
+
+    pub fn update_duty(&self, date: NaiveDate, duty: Duty) -> Result<Duty> {
+        self.runtime.block_on(self.inner.update_duty(date, duty))
+    }
+
+    pub fn remove_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> Result<()> {
+        self.runtime.block_on(self.inner.remove_duty(date, participant_type))
+    }
+
+    pub fn create_reservation(&self, reservation: &ReservationRequest) -> Result<Reservation> {
+        self.runtime.block_on(self.inner.create_reservation(reservation))
+    }
+
+    pub fn update_reservation(&self, id: i32, reservation: &ReservationRequest) -> Result<Reservation> {
+        self.runtime.block_on(self.inner.update_reservation(id, reservation))
+    }
+
+    pub fn cancel_reservation(&self, id: i32) -> Result<()> {
+        self.runtime.block_on(self.inner.cancel_reservation(id))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::transport::Transport;
+    use super::*;
+
+    #[test]
+    fn test_blocking_replay() {
+        let dir = std::env::temp_dir().join(format!("sgbf-client-blocking-{}", std::process::id()));
+        let builder = ClientBuilder::new()
+            .base_url("http://localhost:8080")
+            .transport(Transport::replay(&dir));
+        let client = Client::with_token(builder, "abc123").unwrap();
+        assert_eq!(client.get_token().unwrap(), "abc123");
+        assert!(matches!(client.get_members(), Err(ClientError::Unrecorded(_))));
+    }
+}
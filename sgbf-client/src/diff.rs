@@ -0,0 +1,307 @@
+//! Changes between two snapshots of the calendar.
+//!
+//! Upstream only shows a window of upcoming days that moves forward every day, so only days
+//! present in both snapshots are compared: days that left or entered the window are not changes.
+//! Entries are matched by person through a [`Directory`], so a name spelled differently in the
+//! new snapshot is not reported as one entry removed and another added. Sign-ups are only listed
+//! on the page of each day, [`DayDiff::sign_ups`] compares two copies of it.
+
+use std::collections::HashMap;
+use chrono::NaiveDate;
+use crate::identity::Directory;
+use crate::model::{Day, DayOverview, PersonEntry, Reservation, RosterEntry, Stats};
+use crate::model::time::DateTime;
+
+/// The calendar as seen at one point in time.
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot<'a> {
+    pub days: &'a [DayOverview],
+    pub reservations: &'a [Reservation],
+    /// When the snapshot was taken, reservations that ended before aren't cancelled but over.
+    pub taken_at: DateTime,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DayChange {
+    EntryAdded(PersonEntry),
+    EntryRemoved(PersonEntry),
+    /// The same person's entry with a different type, time frame or notes.
+    EntryChanged { old: PersonEntry, new: PersonEntry },
+    NoteChanged { old: Option<String>, new: Option<String> },
+    CountChanged { old: Stats, new: Stats },
+    SignedUp(RosterEntry),
+    SignUpWithdrawn(RosterEntry),
+    /// The same person's sign-up with a different type or message.
+    SignUpChanged { old: RosterEntry, new: RosterEntry },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayDiff {
+    pub date: NaiveDate,
+    pub changes: Vec<DayChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReservationChange {
+    Added(Reservation),
+    Cancelled(Reservation),
+    /// A different period, aircraft or comments under the same id.
+    Changed { old: Reservation, new: Reservation },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CalendarDiff {
+    /// Only days with changes, in the order of the new snapshot.
+    pub days: Vec<DayDiff>,
+    pub reservations: Vec<ReservationChange>,
+}
+
+impl DayDiff {
+    pub fn new(old: &DayOverview, new: &DayOverview, people: &Directory) -> Self {
+        let mut changes = Vec::new();
+        if old.registered_pilots != new.registered_pilots {
+            changes.push(DayChange::CountChanged {
+                old: old.registered_pilots.clone(),
+                new: new.registered_pilots.clone(),
+            });
+        }
+        if old.note != new.note {
+            changes.push(DayChange::NoteChanged { old: old.note.clone(), new: new.note.clone() });
+        }
+
+        let old_people = old.entries.iter().map(|entry| people.person_id(&entry.name)).collect::<Vec<_>>();
+        let new_people = new.entries.iter().map(|entry| people.person_id(&entry.name)).collect::<Vec<_>>();
+        let mut matches: Vec<Option<usize>> = vec![None; new.entries.len()];
+        let mut matched = vec![false; old.entries.len()];
+        // prefer the same person's entry of the same type, then any other of theirs
+        for same_type in [true, false] {
+            for (i, entry) in new.entries.iter().enumerate() {
+                if matches[i].is_some() {
+                    continue;
+                }
+                let candidate = (0..old.entries.len()).find(|&j| !matched[j]
+                    && old_people[j] == new_people[i]
+                    && (!same_type || old.entries[j].entry_type == entry.entry_type));
+                if let Some(j) = candidate {
+                    matched[j] = true;
+                    matches[i] = Some(j);
+                }
+            }
+        }
+        for (entry, old_entry) in new.entries.iter().zip(matches) {
+            match old_entry.map(|j| &old.entries[j]) {
+                Some(old_entry) if !same_details(old_entry, entry) => changes.push(DayChange::EntryChanged {
+                    old: old_entry.clone(),
+                    new: entry.clone(),
+                }),
+                Some(_) => {}
+                None => changes.push(DayChange::EntryAdded(entry.clone())),
+            }
+        }
+        changes.extend(old.entries.iter().zip(matched)
+            .filter(|(_, matched)| !matched)
+            .map(|(entry, _)| DayChange::EntryRemoved(entry.clone())));
+
+        Self { date: new.date, changes }
+    }
+
+    /// Changes between two copies of a day's sign-ups, matched by person.
+    pub fn sign_ups(date: NaiveDate, old: &Day, new: &Day, people: &Directory) -> Self {
+        let mut old_entries = old.entries.iter()
+            .map(|entry| (people.person_id(&entry.name), entry))
+            .collect::<HashMap<_, _>>();
+        let mut changes = Vec::new();
+        for entry in &new.entries {
+            match old_entries.remove(&people.person_id(&entry.name)) {
+                Some(old_entry) if old_entry.entry_type != entry.entry_type || old_entry.message != entry.message => {
+                    changes.push(DayChange::SignUpChanged { old: old_entry.clone(), new: entry.clone() });
+                }
+                Some(_) => {}
+                None => changes.push(DayChange::SignedUp(entry.clone())),
+            }
+        }
+        // in upstream's order rather than the map's
+        changes.extend(old.entries.iter()
+            .filter(|entry| old_entries.contains_key(&people.person_id(&entry.name)))
+            .map(|entry| DayChange::SignUpWithdrawn(entry.clone())));
+        Self { date, changes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl CalendarDiff {
+    pub fn new(old: Snapshot, new: Snapshot, people: &Directory) -> Self {
+        let old_days = old.days.iter()
+            .map(|day| (day.date, day))
+            .collect::<HashMap<_, _>>();
+        let days = new.days.iter()
+            .filter_map(|day| old_days.get(&day.date).map(|old_day| DayDiff::new(old_day, day, people)))
+            .filter(|diff| !diff.is_empty())
+            .collect();
+
+        let old_reservations = old.reservations.iter()
+            .map(|reservation| (reservation.id, reservation))
+            .collect::<HashMap<_, _>>();
+        let new_reservations = new.reservations.iter()
+            .map(|reservation| (reservation.id, reservation))
+            .collect::<HashMap<_, _>>();
+        let mut reservations = new.reservations.iter()
+            .filter_map(|reservation| match old_reservations.get(&reservation.id) {
+                None => Some(ReservationChange::Added(reservation.clone())),
+                Some(old) if *old != reservation => Some(ReservationChange::Changed {
+                    old: (*old).clone(),
+                    new: reservation.clone(),
+                }),
+                Some(_) => None,
+            })
+            .collect::<Vec<_>>();
+        reservations.extend(old.reservations.iter()
+            .filter(|reservation| !new_reservations.contains_key(&reservation.id))
+            .filter(|reservation| reservation.period.to > new.taken_at)
+            .map(|reservation| ReservationChange::Cancelled(reservation.clone())));
+
+        Self { days, reservations }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty() && self.reservations.is_empty()
+    }
+
+    pub fn day(&self, date: NaiveDate) -> Option<&DayDiff> {
+        self.days.iter().find(|diff| diff.date == date)
+    }
+}
+
+fn same_details(old: &PersonEntry, new: &PersonEntry) -> bool {
+    old.entry_type == new.entry_type
+        && old.time_frame == new.time_frame
+        && old.note_1 == new.note_1
+        && old.note_2 == new.note_2
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{EditAction, EntryType, ParticipantType, Period, RosterEntryType};
+    use crate::model::aircraft::Aircraft;
+    use crate::model::time;
+    use super::*;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 6, day).unwrap()
+    }
+
+    fn entry(name: &str, entry_type: EntryType, time_frame: &str) -> PersonEntry {
+        PersonEntry {
+            time_frame: time_frame.parse().unwrap(),
+            name: name.to_string(),
+            entry_type,
+            note_1: None,
+            note_2: None,
+        }
+    }
+
+    fn day(day: u32, definitive: u32, entries: Vec<PersonEntry>) -> DayOverview {
+        DayOverview {
+            date: date(day),
+            registered_pilots: (definitive, 0).into(),
+            entries,
+            note: None,
+            reservations: None,
+        }
+    }
+
+    fn reservation(id: i32, day: u32) -> Reservation {
+        Reservation {
+            id,
+            period: Period::on(date(day), &"10:00 - 12:00".parse().unwrap()),
+            plane: Aircraft { registration_number: "HB-3187".to_string(), model: "ASK21".to_string(), competition_number: None },
+            reserved_by: "Hans Muster".to_string(),
+            created_at: time::start_of_day(date(1)),
+            comments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_day_diff() {
+        let old = day(4, 3, vec![
+            entry("Hans Muster", EntryType::FlightInstructor, "09:00 - 17:00"),
+            entry("Peter Meier", EntryType::TowingPilot, "09:00 - 13:00"),
+            entry("Anna Keller", EntryType::TowingPilot, "13:00 - 17:00"),
+        ]);
+        let mut new = day(4, 4, vec![
+            // same person, spelled differently
+            entry("Muster Hans", EntryType::FlightInstructor, "09:00 - 17:00"),
+            entry("Peter Meier", EntryType::TowingPilot, "09:00 - 15:00"),
+            entry("Eva Frei", EntryType::WinchOperator, "09:00 - 17:00"),
+        ]);
+        new.note = Some("Fluglager".to_string());
+        let diff = DayDiff::new(&old, &new, &Directory::default());
+        assert_eq!(diff.changes, vec![
+            DayChange::CountChanged { old: (3, 0).into(), new: (4, 0).into() },
+            DayChange::NoteChanged { old: None, new: Some("Fluglager".to_string()) },
+            DayChange::EntryChanged { old: old.entries[1].clone(), new: new.entries[1].clone() },
+            DayChange::EntryAdded(new.entries[2].clone()),
+            DayChange::EntryRemoved(old.entries[2].clone()),
+        ]);
+    }
+
+    #[test]
+    fn test_shifted_window() {
+        let instructor = entry("Hans Muster", EntryType::FlightInstructor, "09:00 - 17:00");
+        let old_days = vec![day(3, 1, vec![]), day(4, 1, vec![]), day(5, 0, vec![])];
+        let new_days = vec![day(4, 1, vec![]), day(5, 0, vec![instructor.clone()]), day(6, 2, vec![])];
+        let old_reservations = vec![reservation(1, 3), reservation(2, 5), reservation(3, 6)];
+        let new_reservations = vec![reservation(3, 6), reservation(4, 6)];
+        let diff = CalendarDiff::new(
+            Snapshot { days: &old_days, reservations: &old_reservations, taken_at: time::start_of_day(date(3)) },
+            Snapshot { days: &new_days, reservations: &new_reservations, taken_at: time::start_of_day(date(4)) },
+            &Directory::default(),
+        );
+        assert_eq!(diff.days, vec![DayDiff { date: date(5), changes: vec![DayChange::EntryAdded(instructor)] }]);
+        // the reservation on the 3rd is over, not cancelled
+        assert_eq!(diff.reservations, vec![
+            ReservationChange::Added(reservation(4, 6)),
+            ReservationChange::Cancelled(reservation(2, 5)),
+        ]);
+    }
+
+    fn sign_up(name: &str, entry_type: RosterEntryType) -> RosterEntry {
+        RosterEntry { name: name.to_string(), message: String::new(), entry_type }
+    }
+
+    fn roster(entries: Vec<RosterEntry>) -> Day {
+        Day {
+            entries,
+            action: EditAction::Add,
+            id: None,
+            participant_type: ParticipantType::GliderPilot,
+            format: "text".to_string(),
+            remarks: None,
+            entry_type: None,
+            reservations: None,
+        }
+    }
+
+    #[test]
+    fn test_sign_ups() {
+        let old = roster(vec![
+            sign_up("Hans Muster", RosterEntryType::Definite),
+            sign_up("Peter Meier", RosterEntryType::Tentative),
+            sign_up("Anna Keller", RosterEntryType::Definite),
+        ]);
+        let new = roster(vec![
+            sign_up("Muster Hans", RosterEntryType::Definite),
+            sign_up("Peter Meier", RosterEntryType::Definite),
+            sign_up("Eva Frei", RosterEntryType::Tentative),
+        ]);
+        let diff = DayDiff::sign_ups(date(4), &old, &new, &Directory::default());
+        assert_eq!(diff.changes, vec![
+            DayChange::SignUpChanged { old: old.entries[1].clone(), new: new.entries[1].clone() },
+            DayChange::SignedUp(new.entries[2].clone()),
+            DayChange::SignUpWithdrawn(old.entries[2].clone()),
+        ]);
+    }
+}
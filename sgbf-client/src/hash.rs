@@ -0,0 +1,5 @@
+/// 64 bit FNV-1a, for file names and fake values that must not change between builds, unlike
+/// those of `DefaultHasher`.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
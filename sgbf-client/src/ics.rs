@@ -0,0 +1,309 @@
+//! iCalendar (RFC 5545) export of the roster, duties and reservations.
+//!
+//! Every event gets a UID derived from what it describes, e.g. the reservation id or the date
+//! and person, so a calendar app that re-imports or refreshes a feed replaces its copy of
+//! an event instead of adding a duplicate. Times are written in UTC, which avoids shipping a
+//! `VTIMEZONE` definition for the club's timezone.
+
+use std::fmt;
+use chrono::{NaiveDate, Utc};
+use crate::identity::PersonId;
+use crate::model::{DayOverview, EntryType, Period, PersonEntry, Reservation, RosterEntry, RosterEntryType};
+use crate::model::time::DateTime;
+
+const PRODUCT_ID: &str = "-//sgbf//sgbf-client//EN";
+const UID_DOMAIN: &str = "sgbf";
+/// Lines longer than this many octets are folded.
+const LINE_LIMIT: usize = 75;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventTime {
+    /// A whole day, ends are exclusive so a single day ends on the following date.
+    Date(NaiveDate),
+    DateTime(DateTime),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventStatus {
+    Tentative,
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub uid: String,
+    pub start: EventTime,
+    pub end: EventTime,
+    pub summary: String,
+    pub description: Option<String>,
+    pub status: Option<EventStatus>,
+}
+
+impl Event {
+    /// An all-day event for a day on the calendar, listing its duties.
+    pub fn flying_day(day: &DayOverview) -> Self {
+        let stats = &day.registered_pilots;
+        let duties = day.entries.iter()
+            .map(|entry| format!("{}: {} ({})", duty_label(&entry.entry_type), entry.name, entry.time_frame));
+        let description = day.note.iter().cloned()
+            .chain(duties)
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self {
+            uid: uid(&["day", &day.date.to_string()]),
+            start: EventTime::Date(day.date),
+            end: EventTime::Date(day.date.succ_opt().unwrap_or(day.date)),
+            summary: format!("Flying day ({} definite, {} tentative)", stats.definitive, stats.tentative),
+            description: (!description.is_empty()).then_some(description),
+            status: None,
+        }
+    }
+
+    /// A duty on the given day. `person` keeps the uid stable when upstream spells the name
+    /// differently, see [`crate::identity::Directory::person_id`]. The duty itself is left out
+    /// of the uid, so a changed duty replaces the event.
+    pub fn duty(date: NaiveDate, entry: &PersonEntry, person: &PersonId) -> Self {
+        let label = duty_label(&entry.entry_type);
+        let period = Period::on(date, &entry.time_frame);
+        let notes = [&entry.note_1, &entry.note_2].into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        Self {
+            uid: uid(&["duty", &date.to_string(), person.as_str()]),
+            start: EventTime::DateTime(period.from),
+            end: EventTime::DateTime(period.to),
+            summary: format!("{}: {}", label, entry.name),
+            description: (!notes.is_empty()).then(|| notes.join("\n")),
+            status: Some(EventStatus::Confirmed),
+        }
+    }
+
+    /// An all-day event for a person's own entry on the roster of the given day.
+    pub fn sign_up(date: NaiveDate, entry: &RosterEntry, person: &PersonId) -> Self {
+        let (summary, status) = match entry.entry_type {
+            RosterEntryType::Definite => ("Flying", Some(EventStatus::Confirmed)),
+            RosterEntryType::Tentative => ("Flying (tentative)", Some(EventStatus::Tentative)),
+            RosterEntryType::Unavailable => ("Unavailable", None),
+        };
+        Self {
+            uid: uid(&["sign-up", &date.to_string(), person.as_str()]),
+            start: EventTime::Date(date),
+            end: EventTime::Date(date.succ_opt().unwrap_or(date)),
+            summary: summary.to_string(),
+            description: (!entry.message.is_empty()).then(|| entry.message.clone()),
+            status,
+        }
+    }
+}
+
+impl From<&Reservation> for Event {
+    fn from(reservation: &Reservation) -> Self {
+        let description = std::iter::once(format!("Reserved by {}", reservation.reserved_by))
+            .chain(reservation.comments.iter().cloned())
+            .collect::<Vec<_>>()
+            .join("\n");
+        Self {
+            uid: uid(&["reservation", &reservation.id.to_string()]),
+            start: EventTime::DateTime(reservation.period.from),
+            end: EventTime::DateTime(reservation.period.to),
+            summary: format!("Reservation {}", reservation.plane),
+            description: Some(description),
+            status: Some(EventStatus::Confirmed),
+        }
+    }
+}
+
+/// A `VCALENDAR` with its events, rendered by its [`fmt::Display`] implementation.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    name: String,
+    stamp: chrono::DateTime<Utc>,
+    events: Vec<Event>,
+}
+
+impl Calendar {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            stamp: Utc::now(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Sets the `DTSTAMP` of all events, which defaults to the time the calendar was created.
+    pub fn with_stamp(mut self, stamp: chrono::DateTime<Utc>) -> Self {
+        self.stamp = stamp;
+        self
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+}
+
+impl Extend<Event> for Calendar {
+    fn extend<T: IntoIterator<Item = Event>>(&mut self, events: T) {
+        self.events.extend(events);
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stamp = self.stamp.format("%Y%m%dT%H%M%SZ").to_string();
+        write_line(f, "BEGIN:VCALENDAR")?;
+        write_line(f, "VERSION:2.0")?;
+        write_line(f, &format!("PRODID:{}", PRODUCT_ID))?;
+        write_line(f, "CALSCALE:GREGORIAN")?;
+        write_line(f, "METHOD:PUBLISH")?;
+        write_line(f, &format!("X-WR-CALNAME:{}", escape(&self.name)))?;
+        // hints for subscribed feeds
+        write_line(f, "REFRESH-INTERVAL;VALUE=DURATION:PT1H")?;
+        write_line(f, "X-PUBLISHED-TTL:PT1H")?;
+        for event in &self.events {
+            write_line(f, "BEGIN:VEVENT")?;
+            write_line(f, &format!("UID:{}", event.uid))?;
+            write_line(f, &format!("DTSTAMP:{}", stamp))?;
+            write_line(f, &event.start.property("DTSTART"))?;
+            write_line(f, &event.end.property("DTEND"))?;
+            write_line(f, &format!("SUMMARY:{}", escape(&event.summary)))?;
+            if let Some(description) = &event.description {
+                write_line(f, &format!("DESCRIPTION:{}", escape(description)))?;
+            }
+            match event.status {
+                Some(EventStatus::Tentative) => write_line(f, "STATUS:TENTATIVE")?,
+                Some(EventStatus::Confirmed) => write_line(f, "STATUS:CONFIRMED")?,
+                None => {}
+            }
+            write_line(f, "END:VEVENT")?;
+        }
+        write_line(f, "END:VCALENDAR")
+    }
+}
+
+impl EventTime {
+    fn property(&self, name: &str) -> String {
+        match self {
+            EventTime::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+            EventTime::DateTime(datetime) => format!("{}:{}", name, datetime.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+fn duty_label(entry_type: &EntryType) -> &str {
+    match entry_type {
+        EntryType::FlightInstructor => "Flight instructor",
+        EntryType::TowingPilot => "Tow pilot",
+        EntryType::WinchOperator => "Winch operator",
+        EntryType::Other(code) => code,
+    }
+}
+
+/// Joins the parts into a uid of lower case ascii letters, digits and dashes.
+fn uid(parts: &[&str]) -> String {
+    let local = parts.iter()
+        .map(|part| part.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("{}@{}", local, UID_DOMAIN)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes a content line, folded so no line exceeds [`LINE_LIMIT`] octets without splitting
+/// a character.
+fn write_line(f: &mut fmt::Formatter<'_>, line: &str) -> fmt::Result {
+    let mut rest = line;
+    // continuation lines start with a space
+    let mut limit = LINE_LIMIT;
+    while rest.len() > limit {
+        let mut at = limit;
+        while !rest.is_char_boundary(at) {
+            at -= 1;
+        }
+        let (head, tail) = rest.split_at(at);
+        write!(f, "{}\r\n ", head)?;
+        rest = tail;
+        limit = LINE_LIMIT - 1;
+    }
+    write!(f, "{}\r\n", rest)
+}
+
+#[cfg(test)]
+mod test {
+    use chrono::TimeZone;
+    use crate::model::aircraft::Aircraft;
+    use crate::model::time;
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let date = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap();
+        let person = PersonId::new("name:hans-muster");
+        let entry = PersonEntry {
+            time_frame: "09:00 - 17:00".parse().unwrap(),
+            name: "Müster, Hans".to_string(),
+            entry_type: EntryType::FlightInstructor,
+            note_1: Some("Schulung; Überlandflug".to_string()),
+            note_2: None,
+        };
+        let reservation = Reservation {
+            id: 42,
+            period: Period::on(date, &"10:00 - 12:00".parse().unwrap()),
+            plane: Aircraft { registration_number: "HB-3187".to_string(), model: "ASK21".to_string(), competition_number: None },
+            reserved_by: "Hans Muster".to_string(),
+            created_at: time::start_of_day(date),
+            comments: vec!["x".repeat(100)],
+        };
+        let mut calendar = Calendar::new("Hans Muster")
+            .with_stamp(Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap());
+        calendar.push(Event::duty(date, &entry, &person));
+        calendar.push(Event::from(&reservation));
+        let ics = calendar.to_string();
+
+        assert!(ics.contains("UID:duty-2023-06-04-name-hans-muster@sgbf\r\n"));
+        // club time is utc+2 in summer
+        assert!(ics.contains("DTSTART:20230604T070000Z\r\nDTEND:20230604T150000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Flight instructor: Müster\\, Hans\r\n"));
+        assert!(ics.contains("DESCRIPTION:Schulung\\; Überlandflug\r\n"));
+        assert!(ics.contains("UID:reservation-42@sgbf\r\n"));
+        assert!(ics.lines().all(|line| line.len() <= LINE_LIMIT));
+        let unfolded = ics.replace("\r\n ", "");
+        assert!(unfolded.contains(&format!("DESCRIPTION:Reserved by Hans Muster\\n{}\r\n", "x".repeat(100))));
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n") && ics.ends_with("END:VCALENDAR\r\n"));
+    }
+
+    #[test]
+    fn test_fold_multibyte() {
+        struct Line<'a>(&'a str);
+        impl fmt::Display for Line<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_line(f, self.0)
+            }
+        }
+        let line = format!("SUMMARY:{}", "ä".repeat(80));
+        let folded = Line(&line).to_string();
+        assert!(folded.split("\r\n").all(|line| line.len() <= LINE_LIMIT));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{}\r\n", line));
+    }
+}
@@ -0,0 +1,288 @@
+//! Resolves the free-text names upstream shows in the calendar, day rosters, reservations and the
+//! member directory to a stable [`PersonId`].
+//!
+//! Names are compared as sets of normalised tokens, so "Muster Hans", "Hans Muster" and
+//! "muster, hans" are the same person. A token may also be abbreviated to its initial ("H. Muster")
+//! or carry a small typo. Names that can't be matched unambiguously can be pinned with overrides.
+
+use std::collections::HashMap;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::model::Member;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct PersonId(String);
+
+impl PersonId {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for PersonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// A name as lower case ascii tokens, in their original order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NameKey(Vec<String>);
+
+impl NameKey {
+    pub fn new(name: &str) -> Self {
+        let folded = name.chars().map(fold).collect::<String>();
+        let tokens = folded.split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(|token| token.to_string())
+            .collect();
+        Self(tokens)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn sorted(&self) -> Vec<&str> {
+        let mut tokens = self.0.iter().map(String::as_str).collect::<Vec<_>>();
+        tokens.sort_unstable();
+        tokens
+    }
+}
+
+fn fold(c: char) -> String {
+    let folded = match c {
+        'ä' | 'Ä' => "ae",
+        'ö' | 'Ö' => "oe",
+        'ü' | 'Ü' => "ue",
+        'ß' => "ss",
+        'à' | 'á' | 'â' | 'À' | 'Á' | 'Â' => "a",
+        'è' | 'é' | 'ê' | 'ë' | 'È' | 'É' | 'Ê' | 'Ë' => "e",
+        'ì' | 'í' | 'î' | 'ï' => "i",
+        'ò' | 'ó' | 'ô' => "o",
+        'ù' | 'ú' | 'û' => "u",
+        'ç' | 'Ç' => "c",
+        c => return c.to_lowercase().collect(),
+    };
+    folded.to_string()
+}
+
+/// How a name was matched, from least to most certain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MatchKind {
+    /// Tokens differ by a typo.
+    Fuzzy,
+    /// Some tokens are initials or missing, e.g. a middle name.
+    Abbreviated,
+    /// Same tokens in a different order.
+    Reordered,
+    Exact,
+    Override,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub id: PersonId,
+    pub kind: MatchKind,
+}
+
+/// The known people, usually built from the member directory.
+#[derive(Debug, Clone, Default)]
+pub struct Directory {
+    people: Vec<(PersonId, NameKey)>,
+    overrides: HashMap<Vec<String>, PersonId>,
+}
+
+impl Directory {
+    pub fn new(members: &[Member]) -> Self {
+        let people = members.iter()
+            .map(|member| (PersonId::new(member.id.clone()), NameKey::new(&member.name)))
+            .filter(|(_, key)| !key.is_empty())
+            .collect();
+        Self {
+            people,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Always resolves `name`, in any token order, to `id`.
+    pub fn with_override(mut self, name: &str, id: PersonId) -> Self {
+        let key = NameKey::new(name).sorted().into_iter().map(String::from).collect();
+        self.overrides.insert(key, id);
+        self
+    }
+
+    /// The best matching person, `None` if nobody or more than one person matches equally well.
+    pub fn resolve(&self, name: &str) -> Option<Match> {
+        let key = NameKey::new(name);
+        if key.is_empty() {
+            return None;
+        }
+        let sorted = key.sorted().into_iter().map(String::from).collect::<Vec<_>>();
+        if let Some(id) = self.overrides.get(&sorted) {
+            return Some(Match { id: id.clone(), kind: MatchKind::Override });
+        }
+        let mut best: Option<Match> = None;
+        let mut ambiguous = false;
+        for (id, candidate) in &self.people {
+            let Some(kind) = compare(&key, candidate) else {
+                continue;
+            };
+            match &best {
+                Some(current) if current.kind > kind => {}
+                Some(current) if current.kind == kind => ambiguous |= current.id != *id,
+                _ => {
+                    best = Some(Match { id: id.clone(), kind });
+                    ambiguous = false;
+                }
+            }
+        }
+        best.filter(|_| !ambiguous)
+    }
+
+    /// The resolved id, or one derived from the name if it can't be resolved. Derived ids don't
+    /// depend on token order, so they are stable as long as upstream spells the name the same way.
+    pub fn person_id(&self, name: &str) -> PersonId {
+        match self.resolve(name) {
+            Some(found) => found.id,
+            None => PersonId(format!("name:{}", NameKey::new(name).sorted().join("-"))),
+        }
+    }
+
+    pub fn same_person(&self, a: &str, b: &str) -> bool {
+        self.person_id(a) == self.person_id(b)
+    }
+}
+
+fn compare(query: &NameKey, candidate: &NameKey) -> Option<MatchKind> {
+    if query == candidate {
+        return Some(MatchKind::Exact);
+    }
+    if query.sorted() == candidate.sorted() {
+        return Some(MatchKind::Reordered);
+    }
+    // every token of the shorter name has to match a different token of the longer one
+    let (short, long) = match query.0.len() <= candidate.0.len() {
+        true => (&query.0, &candidate.0),
+        false => (&candidate.0, &query.0),
+    };
+    if short.len() < 2 {
+        return None;
+    }
+    let mut used = vec![false; long.len()];
+    let mut exact = 0;
+    let mut fuzzy = false;
+    for token in short {
+        let found = long.iter().enumerate()
+            .filter(|(index, _)| !used[*index])
+            .filter_map(|(index, other)| token_match(token, other).map(|kind| (index, kind)))
+            .max_by_key(|(_, kind)| *kind)?;
+        used[found.0] = true;
+        match found.1 {
+            TokenMatch::Exact => exact += 1,
+            TokenMatch::Fuzzy => fuzzy = true,
+            TokenMatch::Initial => {}
+        }
+    }
+    // initials and typos alone are not enough
+    if exact == 0 {
+        return None;
+    }
+    Some(if fuzzy { MatchKind::Fuzzy } else { MatchKind::Abbreviated })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TokenMatch {
+    Initial,
+    Fuzzy,
+    Exact,
+}
+
+fn token_match(a: &str, b: &str) -> Option<TokenMatch> {
+    if a == b {
+        return Some(TokenMatch::Exact);
+    }
+    let is_initial = |initial: &str, token: &str| initial.len() == 1 && token.starts_with(initial);
+    if is_initial(a, b) || is_initial(b, a) {
+        return Some(TokenMatch::Initial);
+    }
+    if a.len().min(b.len()) >= 5 && levenshtein(a, b) <= 1 {
+        return Some(TokenMatch::Fuzzy);
+    }
+    None
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::Addresses;
+    use super::*;
+
+    fn member(id: &str, name: &str) -> Member {
+        Member {
+            id: id.to_string(),
+            name: name.to_string(),
+            address: None,
+            private: Addresses::default(),
+            office: Addresses::default(),
+            role: None,
+        }
+    }
+
+    fn directory() -> Directory {
+        Directory::new(&[
+            member("1", "Muster Hans"),
+            member("2", "Müller Anna Maria"),
+            member("3", "Keller Peter"),
+            member("4", "Keller Paul"),
+        ])
+    }
+
+    #[test]
+    fn test_resolve() {
+        let directory = directory();
+        let resolve = |name| directory.resolve(name).map(|found| (found.id.0, found.kind));
+        assert_eq!(resolve("Muster Hans"), Some(("1".to_string(), MatchKind::Exact)));
+        assert_eq!(resolve("hans muster"), Some(("1".to_string(), MatchKind::Reordered)));
+        assert_eq!(resolve("Muster, Hans"), Some(("1".to_string(), MatchKind::Exact)));
+        assert_eq!(resolve("H. Muster"), Some(("1".to_string(), MatchKind::Abbreviated)));
+        assert_eq!(resolve("Anna Mueller"), Some(("2".to_string(), MatchKind::Abbreviated)));
+        assert_eq!(resolve("Mueller Ana"), None);
+        assert_eq!(resolve("Muster Hanss"), None);
+        assert_eq!(resolve("Mustre Hans"), None);
+        assert_eq!(resolve("Müler Anna"), Some(("2".to_string(), MatchKind::Fuzzy)));
+        // two Kellers with the initial P
+        assert_eq!(resolve("P. Keller"), None);
+        assert_eq!(resolve("Keller"), None);
+    }
+
+    #[test]
+    fn test_overrides_and_derived_ids() {
+        let directory = directory().with_override("Kelli", PersonId::new("3"));
+        assert_eq!(directory.person_id("kelli").as_str(), "3");
+        assert_eq!(directory.person_id("Gast Fritz").as_str(), "name:fritz-gast");
+        assert!(directory.same_person("Fritz Gast", "Gast Fritz"));
+        assert!(directory.same_person("Muster Hans", "H. Muster"));
+        assert!(!directory.same_person("Keller Peter", "Keller Paul"));
+    }
+}
@@ -2,5 +2,15 @@
 pub mod parsing;
 pub mod client;
 pub mod model;
+pub mod transport;
+pub mod anonymize;
+pub mod identity;
+pub mod limiter;
+pub mod source;
+pub mod ics;
+pub mod diff;
+mod hash;
+#[cfg(feature = "schema")]
+pub mod schema;
 
 pub use client::Client;
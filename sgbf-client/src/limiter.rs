@@ -0,0 +1,159 @@
+//! Keeps the load on upstream polite.
+//!
+//! A [`RateLimiter`] is cheap to clone and meant to be shared by every [`crate::Client`] in the
+//! process: it spaces requests out to at most `requests_per_second` and caps how many are in
+//! flight at once. [`RetryPolicy`] decides how often and how long to back off before repeating a
+//! request that failed for a transient reason. Only reads are retried, including the `POST`s that
+//! query the calendar and reservations, writes never are.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use serde::Deserialize;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64,
+    pub max_concurrent: usize,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_second: 5.0,
+            max_concurrent: 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: Arc<Mutex<Instant>>,
+    in_flight: Arc<Semaphore>,
+}
+
+/// Held while a request is in flight.
+#[derive(Debug)]
+pub struct Permit(#[allow(dead_code)] OwnedSemaphorePermit);
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    /// A zero or negative rate disables spacing, concurrency is always limited to at least one.
+    pub fn new(config: RateLimitConfig) -> Self {
+        let interval = match config.requests_per_second > 0.0 {
+            true => Duration::from_secs_f64(1.0 / config.requests_per_second),
+            false => Duration::ZERO,
+        };
+        Self {
+            interval,
+            next_slot: Arc::new(Mutex::new(Instant::now())),
+            in_flight: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+        }
+    }
+
+    /// The limiter clients use unless they are given another one.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<RateLimiter> = OnceLock::new();
+        SHARED.get_or_init(RateLimiter::default).clone()
+    }
+
+    /// Waits for a free slot and a free request.
+    pub async fn acquire(&self) -> Permit {
+        let permit = self.in_flight.clone().acquire_owned().await
+            .expect("semaphore is never closed");
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + self.interval;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+        Permit(permit)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, zero disables retrying.
+    pub max_retries: u32,
+    #[serde(rename = "initial_backoff_ms", with = "millis")]
+    pub initial_backoff: Duration,
+    #[serde(rename = "max_backoff_ms", with = "millis")]
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Default::default() }
+    }
+
+    /// How long to wait before the given retry, starting at zero. Doubles with every retry.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+// durations in config files are given in milliseconds
+mod millis {
+    use std::time::Duration;
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        u64::deserialize(deserializer).map(Duration::from_millis)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let policy = RetryPolicy::default();
+        let backoff = (0..6).map(|retry| policy.backoff(retry).as_millis()).collect::<Vec<_>>();
+        assert_eq!(backoff, vec![250, 500, 1000, 2000, 4000, 5000]);
+        assert_eq!(policy.backoff(u32::MAX), policy.max_backoff);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_spacing_and_concurrency() {
+        let limiter = RateLimiter::new(RateLimitConfig { requests_per_second: 2.0, max_concurrent: 2 });
+        let start = Instant::now();
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+        assert_eq!(start.elapsed(), Duration::from_millis(500));
+        // the third request has to wait for one of the first two to finish
+        let third = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                limiter.acquire().await;
+                Instant::now()
+            }
+        });
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        drop(first);
+        let acquired = third.await.unwrap();
+        assert_eq!(acquired - start, Duration::from_millis(2500));
+    }
+}
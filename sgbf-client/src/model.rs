@@ -1,11 +1,17 @@
 pub mod aircraft;
+pub mod period;
+pub mod query;
+pub mod time;
+pub mod time_frame;
 
-use std::fmt::Display;
-use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use crate::model::aircraft::Aircraft;
 
+pub use period::{Contains, Overlaps, Period};
+pub use time_frame::TimeFrame;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Day {
     pub entries: Vec<RosterEntry>,
@@ -18,20 +24,64 @@ pub struct Day {
     pub reservations: Option<Vec<Reservation>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "lowercase")]
 pub enum EditAction {
     Edit,
     Add,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ParticipantType {
     #[serde(rename = "participant_sf")]
     GliderPilot,
+    #[serde(rename = "participant_fi")]
+    FlightInstructor,
+    #[serde(rename = "participant_s")]
+    TowingPilot,
+    #[serde(rename = "participant_w")]
+    WinchOperator,
 }
 
+impl ParticipantType {
+    /// Value of the upstream `fe_t` parameter.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParticipantType::GliderPilot => "participant_sf",
+            ParticipantType::FlightInstructor => "participant_fi",
+            ParticipantType::TowingPilot => "participant_s",
+            ParticipantType::WinchOperator => "participant_w",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "participant_sf" => Some(ParticipantType::GliderPilot),
+            "participant_fi" => Some(ParticipantType::FlightInstructor),
+            "participant_s" => Some(ParticipantType::TowingPilot),
+            "participant_w" => Some(ParticipantType::WinchOperator),
+            _ => None,
+        }
+    }
+}
+
+/// A duty slot of a flight instructor, tow pilot or winch operator on a given day.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Duty {
+    pub action: EditAction,
+    pub id: Option<i32>,
+    pub participant_type: ParticipantType,
+    pub format: String,
+    pub time_frame: Option<TimeFrame>,
+    pub remarks: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct RosterEntry {
     pub name: String,
@@ -40,6 +90,7 @@ pub struct RosterEntry {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "PascalCase")]
 pub enum RosterEntryType {
     Definite,
@@ -48,6 +99,7 @@ pub enum RosterEntryType {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct DayOverview {
     pub date: chrono::NaiveDate,
@@ -57,7 +109,8 @@ pub struct DayOverview {
     pub reservations: Option<Vec<Reservation>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Stats {
     pub definitive: u32,
     pub tentative: u32,
@@ -72,7 +125,8 @@ impl From<(u32, u32)> for Stats {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct PersonEntry {
     pub time_frame: TimeFrame,
@@ -82,9 +136,8 @@ pub struct PersonEntry {
     pub note_2: Option<String>,
 }
 
-pub type TimeFrame = (chrono::NaiveTime, chrono::NaiveTime);
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
 pub enum EntryType {
     #[serde(rename = "FlightInstructor")]
     FlightInstructor,
@@ -92,72 +145,126 @@ pub enum EntryType {
     TowingPilot,
     #[serde(rename = "WinchOperator")]
     WinchOperator,
+    /// an entry type the client doesn't know yet, holding the upstream code
+    #[serde(untagged)]
+    Other(String),
+}
+
+// schemars doesn't support untagged variants
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for EntryType {
+    fn schema_name() -> String {
+        "EntryType".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        use schemars::schema::{InstanceType, Metadata, SchemaObject, SubschemaValidation};
+        let known = SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            enum_values: Some(vec!["FlightInstructor".into(), "TowingPilot".into(), "WinchOperator".into()]),
+            ..Default::default()
+        };
+        let mut other = generator.subschema_for::<String>().into_object();
+        other.metadata = Some(Box::new(Metadata {
+            description: Some("an entry type the client doesn't know yet, holding the upstream code".to_string()),
+            ..Default::default()
+        }));
+        SchemaObject {
+            subschemas: Some(Box::new(SubschemaValidation {
+                any_of: Some(vec![known.into(), other.into()]),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }.into()
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Member {
+    /// Upstream's member id if the directory links one, otherwise derived from the name.
+    pub id: String,
     pub name: String,
-    pub address: Option<String>,
+    pub address: Option<Address>,
     pub private: Addresses,
     pub office: Addresses,
+    pub role: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct Addresses {
-    pub phone: Option<String>,
-    pub email: Option<String>,
-    pub mobile: Option<String>,
-}
-
-#[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct Period {
-    pub from: chrono::NaiveDateTime,
-    pub to: chrono::NaiveDateTime,
-}
-
-impl From<NaiveDate> for Period {
-    fn from(date: NaiveDate) -> Self {
-        let date_end = date.and_hms_opt(23, 59, 59);
-        let date_start = date.and_hms_opt(0, 0, 0);
-        Self {
-            from: date_start.unwrap(),
-            to: date_end.unwrap(),
-        }
+impl Member {
+    /// Whether name, city, postcode, role, emails or phone numbers contain `query`, ignoring case
+    /// and whitespace.
+    pub fn matches(&self, query: &str) -> bool {
+        let normalize = |value: &str| value.chars()
+            .filter(|c| !c.is_whitespace())
+            .flat_map(char::to_lowercase)
+            .collect::<String>();
+        let query = normalize(query);
+        let address = self.address.as_ref();
+        let contacts = [&self.private, &self.office].into_iter()
+            .flat_map(|contacts| [&contacts.phone, &contacts.fax, &contacts.mobile, &contacts.email]);
+        [Some(&self.name), address.and_then(|a| a.city.as_ref()), address.and_then(|a| a.postcode.as_ref()), self.role.as_ref()]
+            .into_iter()
+            .chain(contacts.map(Option::as_ref))
+            .flatten()
+            .any(|value| normalize(value).contains(&query))
     }
 }
 
-pub trait Overlaps<T> {
-    fn overlaps(&self, other: &T) -> bool;
-}
-
-impl Overlaps<NaiveDate> for Period {
-    fn overlaps(&self, other: &NaiveDate) -> bool {
-        let other = Period::from(*other);
-        self.from <= other.to && other.from <= self.to
-    }
-}
-
-impl Overlaps<Period> for Period {
-    fn overlaps(&self, other: &Period) -> bool {
-        self.from <= other.to && other.from >= self.to
-    }
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Address {
+    pub street: Option<String>,
+    pub postcode: Option<String>,
+    pub city: Option<String>,
 }
 
-impl Display for Period {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} - {}", self.from, self.to)
-    }
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct Addresses {
+    pub phone: Option<String>,
+    pub fax: Option<String>,
+    pub email: Option<String>,
+    pub mobile: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Reservation {
     pub id: i32,
     pub period: Period,
     pub plane: Aircraft,
     pub reserved_by: String,
-    pub created_at: chrono::NaiveDate,
+    /// Start of the day the reservation was made, upstream doesn't show the time.
+    #[serde(with = "time::rfc3339")]
+    #[cfg_attr(feature = "schema", schemars(with = "chrono::DateTime<chrono::FixedOffset>"))]
+    pub created_at: time::DateTime,
     pub comments: Vec<String>
 }
+
+/// A reservation to be created or modified upstream.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationRequest {
+    pub plane: Aircraft,
+    pub period: Period,
+    pub comment: Option<String>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_entry_type_serde() {
+        assert_eq!(serde_json::to_string(&EntryType::TowingPilot).unwrap(), "\"TowingPilot\"");
+        assert_eq!(serde_json::to_string(&EntryType::Other("M".to_string())).unwrap(), "\"M\"");
+        assert_eq!(serde_json::from_str::<EntryType>("\"FlightInstructor\"").unwrap(), EntryType::FlightInstructor);
+        assert_eq!(serde_json::from_str::<EntryType>("\"M\"").unwrap(), EntryType::Other("M".to_string()));
+    }
+}
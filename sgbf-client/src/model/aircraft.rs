@@ -5,6 +5,7 @@ use serde::{Serialize, Deserialize};
 use thiserror::Error;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(rename_all = "camelCase")]
 pub struct Aircraft {
     pub registration_number: String,
@@ -49,6 +50,109 @@ impl TryFrom<&str> for Aircraft {
     }
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "PascalCase")]
+pub enum AircraftClass {
+    SingleSeater,
+    TwoSeater,
+    MotorGlider,
+    Tug,
+    Unknown,
+}
+
+// upstream doesn't say what an aircraft is, so class and seats are looked up from its model.
+// motorised variants share a prefix with the pure glider and need their own entry.
+const MODELS: &[(&str, AircraftClass, u8)] = &[
+    ("ASK21MI", AircraftClass::MotorGlider, 2),
+    ("DG1000T", AircraftClass::MotorGlider, 2),
+    ("DG1001M", AircraftClass::MotorGlider, 2),
+    ("DG1001T", AircraftClass::MotorGlider, 2),
+    ("DUOXM", AircraftClass::MotorGlider, 2),
+    ("DUOXT", AircraftClass::MotorGlider, 2),
+    ("ARCUSM", AircraftClass::MotorGlider, 2),
+    ("ARCUST", AircraftClass::MotorGlider, 2),
+    ("ARCUSE", AircraftClass::MotorGlider, 2),
+    ("ASG32MI", AircraftClass::MotorGlider, 2),
+    ("ASG32EL", AircraftClass::MotorGlider, 2),
+    ("ASG29E", AircraftClass::MotorGlider, 1),
+    ("LS8T", AircraftClass::MotorGlider, 1),
+    ("DISCUS2CT", AircraftClass::MotorGlider, 1),
+    ("DISCUS2T", AircraftClass::MotorGlider, 1),
+    ("VENTUS2CM", AircraftClass::MotorGlider, 1),
+    ("VENTUS2CT", AircraftClass::MotorGlider, 1),
+    ("G109", AircraftClass::MotorGlider, 2),
+    ("SF25", AircraftClass::MotorGlider, 2),
+    ("DIMONA", AircraftClass::MotorGlider, 2),
+    ("ASK21", AircraftClass::TwoSeater, 2),
+    ("ASK13", AircraftClass::TwoSeater, 2),
+    ("DG1000", AircraftClass::TwoSeater, 2),
+    ("DG1001", AircraftClass::TwoSeater, 2),
+    ("DG505", AircraftClass::TwoSeater, 2),
+    ("DUO", AircraftClass::TwoSeater, 2),
+    ("ARCUS", AircraftClass::TwoSeater, 2),
+    ("ASG32", AircraftClass::TwoSeater, 2),
+    ("LS1", AircraftClass::SingleSeater, 1),
+    ("LS3", AircraftClass::SingleSeater, 1),
+    ("LS4", AircraftClass::SingleSeater, 1),
+    ("LS6", AircraftClass::SingleSeater, 1),
+    ("LS8", AircraftClass::SingleSeater, 1),
+    ("ASW", AircraftClass::SingleSeater, 1),
+    ("ASG29", AircraftClass::SingleSeater, 1),
+    ("DISCUS", AircraftClass::SingleSeater, 1),
+    ("VENTUS", AircraftClass::SingleSeater, 1),
+    ("DR400", AircraftClass::Tug, 4),
+    ("PA18", AircraftClass::Tug, 2),
+    ("PA25", AircraftClass::Tug, 1),
+    ("HUSKY", AircraftClass::Tug, 2),
+];
+
+/// The entry with the longest prefix of the model, e.g. "ARCUS M" is the motor glider and not
+/// the "ARCUS" two-seater.
+fn lookup(model: &str) -> Option<(AircraftClass, u8)> {
+    let model = model.to_uppercase().replace(['-', ' ', '.'], "");
+    MODELS.iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())
+        .map(|(_, class, seats)| (*class, *seats))
+}
+
+impl AircraftClass {
+    /// Looks up the class by model prefix, e.g. "DG1001C" is a two-seater.
+    pub fn from_model(model: &str) -> Self {
+        lookup(model)
+            .map(|(class, _)| class)
+            .unwrap_or(AircraftClass::Unknown)
+    }
+}
+
+/// An aircraft of the club's fleet, as offered on the reservation page.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAircraft {
+    #[serde(flatten)]
+    pub aircraft: Aircraft,
+    pub class: AircraftClass,
+    pub seats: Option<u8>,
+    /// Disabled entries are listed upstream but can't be reserved.
+    pub reservable: bool,
+}
+
+impl FleetAircraft {
+    pub fn new(aircraft: Aircraft, reservable: bool) -> Self {
+        let (class, seats) = match lookup(&aircraft.model) {
+            Some((class, seats)) => (class, Some(seats)),
+            None => (AircraftClass::Unknown, None),
+        };
+        Self {
+            aircraft,
+            class,
+            seats,
+            reservable,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -110,6 +214,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_aircraft_class() {
+        assert_eq!(AircraftClass::from_model("LS4"), AircraftClass::SingleSeater);
+        assert_eq!(AircraftClass::from_model("DG1001C"), AircraftClass::TwoSeater);
+        assert_eq!(AircraftClass::from_model("DG1001M"), AircraftClass::MotorGlider);
+        assert_eq!(AircraftClass::from_model("G109"), AircraftClass::MotorGlider);
+        assert_eq!(AircraftClass::from_model("DR400"), AircraftClass::Tug);
+        assert_eq!(AircraftClass::from_model("XYZ"), AircraftClass::Unknown);
+    }
+
+    #[test]
+    fn test_motor_variants() {
+        assert_eq!(AircraftClass::from_model("ARCUS"), AircraftClass::TwoSeater);
+        assert_eq!(AircraftClass::from_model("ARCUS M"), AircraftClass::MotorGlider);
+        assert_eq!(AircraftClass::from_model("ASG32"), AircraftClass::TwoSeater);
+        assert_eq!(AircraftClass::from_model("ASG32MI"), AircraftClass::MotorGlider);
+        assert_eq!(AircraftClass::from_model("DG1000S"), AircraftClass::TwoSeater);
+        assert_eq!(AircraftClass::from_model("DG1000T"), AircraftClass::MotorGlider);
+    }
+
+    #[test]
+    fn test_seats() {
+        let fleet = |model: &str| FleetAircraft::new(Aircraft {
+            registration_number: "HB-0000".to_string(),
+            model: model.to_string(),
+            competition_number: None,
+        }, true);
+        assert_eq!(fleet("DR400").seats, Some(4));
+        assert_eq!(fleet("PA25").seats, Some(1));
+        assert_eq!(fleet("ASG29E").seats, Some(1));
+        assert_eq!(fleet("ARCUS M").seats, Some(2));
+        assert_eq!(fleet("XYZ").seats, None);
+    }
+
     #[test]
     fn test_invalid_format() {
         let invalid_input = "invalid format";
@@ -0,0 +1,279 @@
+use std::fmt::Display;
+use chrono::{Duration, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+use crate::model::TimeFrame;
+use crate::model::time::{self, DateTime};
+
+/// A span of time from `from` up to, but not including, `to`. Periods where `to` is not after
+/// `from` are empty: they cover no time and overlap nothing.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Period {
+    #[serde(with = "time::rfc3339")]
+    #[cfg_attr(feature = "schema", schemars(with = "chrono::DateTime<chrono::FixedOffset>"))]
+    pub from: DateTime,
+    #[serde(with = "time::rfc3339")]
+    #[cfg_attr(feature = "schema", schemars(with = "chrono::DateTime<chrono::FixedOffset>"))]
+    pub to: DateTime,
+}
+
+impl From<NaiveDate> for Period {
+    /// The whole day in the club's timezone, from midnight to the following midnight.
+    fn from(date: NaiveDate) -> Self {
+        let from = time::start_of_day(date);
+        let to = date.succ_opt()
+            .map(time::start_of_day)
+            .unwrap_or_else(|| time::localize(date.and_time(NaiveTime::MIN) + Duration::days(1)));
+        Self { from, to }
+    }
+}
+
+impl Period {
+    /// Returns `None` if `to` is before `from`.
+    pub fn new(from: DateTime, to: DateTime) -> Option<Self> {
+        (from <= to).then_some(Self { from, to })
+    }
+
+    /// The wall-clock time frame on the given day.
+    pub fn on(date: NaiveDate, time_frame: &TimeFrame) -> Self {
+        Self {
+            from: time::localize(date.and_time(time_frame.start())),
+            to: time::localize(date.and_time(time_frame.end())),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.from >= self.to
+    }
+
+    pub fn duration(&self) -> Duration {
+        if self.is_empty() {
+            return Duration::zero();
+        }
+        self.to - self.from
+    }
+
+    /// The time covered by both periods, if any.
+    pub fn intersection(&self, other: &Period) -> Option<Period> {
+        let from = self.from.max(other.from);
+        let to = self.to.min(other.to);
+        (from < to).then_some(Period { from, to })
+    }
+
+    /// The union of two periods that overlap or touch, `None` if there is a gap between them.
+    pub fn merge(&self, other: &Period) -> Option<Period> {
+        if self.is_empty() {
+            return Some(other.clone());
+        }
+        if other.is_empty() {
+            return Some(self.clone());
+        }
+        (self.from <= other.to && other.from <= self.to).then(|| Period {
+            from: self.from.min(other.from),
+            to: self.to.max(other.to),
+        })
+    }
+
+    /// Merges overlapping and touching periods and drops empty ones. The result is sorted and
+    /// no two periods in it overlap or touch.
+    pub fn merge_all(periods: impl IntoIterator<Item = Period>) -> Vec<Period> {
+        let mut periods = periods.into_iter()
+            .filter(|period| !period.is_empty())
+            .collect::<Vec<_>>();
+        periods.sort_by_key(|period| period.from);
+        let mut merged: Vec<Period> = Vec::with_capacity(periods.len());
+        for period in periods {
+            match merged.last_mut() {
+                Some(last) if period.from <= last.to => last.to = last.to.max(period.to),
+                _ => merged.push(period),
+            }
+        }
+        merged
+    }
+
+    /// The calendar days this period covers at least partially.
+    pub fn days(&self) -> impl Iterator<Item = NaiveDate> {
+        let first = self.from.date_naive();
+        // a period ending at midnight does not cover the following day
+        let last = match self.to.time() == NaiveTime::MIN {
+            true => self.to.date_naive().pred_opt().unwrap_or(NaiveDate::MIN),
+            false => self.to.date_naive(),
+        };
+        let empty = self.is_empty();
+        first.iter_days().take_while(move |day| !empty && *day <= last)
+    }
+
+    /// Splits the period at midnight, into one part per day covered.
+    pub fn split_days(&self) -> Vec<Period> {
+        self.days()
+            .filter_map(|day| self.intersection(&Period::from(day)))
+            .collect()
+    }
+}
+
+pub trait Overlaps<T> {
+    fn overlaps(&self, other: &T) -> bool;
+}
+
+impl Overlaps<NaiveDate> for Period {
+    fn overlaps(&self, other: &NaiveDate) -> bool {
+        self.overlaps(&Period::from(*other))
+    }
+}
+
+impl Overlaps<Period> for Period {
+    fn overlaps(&self, other: &Period) -> bool {
+        self.intersection(other).is_some()
+    }
+}
+
+pub trait Contains<T> {
+    fn contains(&self, other: &T) -> bool;
+}
+
+impl Contains<DateTime> for Period {
+    fn contains(&self, other: &DateTime) -> bool {
+        self.from <= *other && *other < self.to
+    }
+}
+
+impl Contains<Period> for Period {
+    fn contains(&self, other: &Period) -> bool {
+        other.is_empty() || (self.from <= other.from && other.to <= self.to)
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.from.format("%Y-%m-%d %H:%M"), self.to.format("%Y-%m-%d %H:%M"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+    use super::*;
+
+    fn at(date: &str, clock: &str) -> DateTime {
+        let naive = chrono::NaiveDateTime::parse_from_str(&format!("{} {}", date, clock), "%Y-%m-%d %H:%M").unwrap();
+        time::localize(naive)
+    }
+
+    fn datetime() -> impl Strategy<Value = DateTime> {
+        // a few weeks around a daylight saving change, in minutes
+        (0i64..60 * 24 * 60).prop_map(|minutes| at("2023-03-01", "00:00") + Duration::minutes(minutes))
+    }
+
+    fn period() -> impl Strategy<Value = Period> {
+        (datetime(), 0i64..5 * 24 * 60)
+            .prop_map(|(from, minutes)| Period { from, to: from + Duration::minutes(minutes) })
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let morning = Period { from: at("2023-07-16", "09:00"), to: at("2023-07-16", "12:00") };
+        let noon = Period { from: at("2023-07-16", "11:00"), to: at("2023-07-16", "14:00") };
+        let afternoon = Period { from: at("2023-07-16", "12:00"), to: at("2023-07-16", "18:00") };
+        assert!(morning.overlaps(&noon));
+        assert!(noon.overlaps(&afternoon));
+        // touching periods don't conflict
+        assert!(!morning.overlaps(&afternoon));
+        assert!(morning.overlaps(&NaiveDate::from_ymd_opt(2023, 7, 16).unwrap()));
+        assert!(!morning.overlaps(&NaiveDate::from_ymd_opt(2023, 7, 17).unwrap()));
+    }
+
+    #[test]
+    fn test_day_across_dst() {
+        let day = Period::from(NaiveDate::from_ymd_opt(2023, 3, 26).unwrap());
+        assert_eq!(day.duration(), Duration::hours(23));
+        assert_eq!(day.days().count(), 1);
+        let day = Period::from(NaiveDate::from_ymd_opt(2023, 10, 29).unwrap());
+        assert_eq!(day.duration(), Duration::hours(25));
+    }
+
+    #[test]
+    fn test_serde() {
+        let period = Period { from: at("2023-07-16", "11:00"), to: at("2023-07-16", "18:00") };
+        let json = serde_json::to_string(&period).unwrap();
+        assert_eq!(json, r#"{"from":"2023-07-16T11:00:00+02:00","to":"2023-07-16T18:00:00+02:00"}"#);
+        assert_eq!(serde_json::from_str::<Period>(&json).unwrap(), period);
+        // naive json from older versions
+        let naive = r#"{"from":"2023-07-16T11:00:00","to":"2023-07-16T18:00:00"}"#;
+        assert_eq!(serde_json::from_str::<Period>(naive).unwrap(), period);
+    }
+
+    #[test]
+    fn test_split_days() {
+        let period = Period { from: at("2023-07-17", "09:00"), to: at("2023-07-19", "00:00") };
+        assert_eq!(period.split_days(), vec![
+            Period { from: at("2023-07-17", "09:00"), to: at("2023-07-18", "00:00") },
+            Period { from: at("2023-07-18", "00:00"), to: at("2023-07-19", "00:00") },
+        ]);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_overlaps_matches_intersection(a in period(), b in period()) {
+            prop_assert_eq!(a.overlaps(&b), b.overlaps(&a));
+            prop_assert_eq!(a.overlaps(&b), a.intersection(&b).is_some());
+            prop_assert_eq!(a.intersection(&b), b.intersection(&a));
+        }
+
+        #[test]
+        fn prop_intersection_is_contained(a in period(), b in period()) {
+            if let Some(intersection) = a.intersection(&b) {
+                prop_assert!(a.contains(&intersection));
+                prop_assert!(b.contains(&intersection));
+                prop_assert!(intersection.duration() <= a.duration().min(b.duration()));
+            }
+        }
+
+        #[test]
+        fn prop_contains_datetime(a in period(), t in datetime()) {
+            let point = Period { from: t, to: t + Duration::minutes(1) };
+            prop_assert_eq!(a.contains(&t), a.contains(&point));
+            prop_assert_eq!(a.contains(&t), a.overlaps(&point));
+        }
+
+        #[test]
+        fn prop_merge_covers_both(a in period(), b in period()) {
+            match a.merge(&b) {
+                Some(merged) => {
+                    prop_assert!(merged.contains(&a));
+                    prop_assert!(merged.contains(&b));
+                    prop_assert_eq!(merged.duration(), a.duration() + b.duration() - a.intersection(&b).map(|i| i.duration()).unwrap_or_else(Duration::zero));
+                }
+                None => prop_assert!(!a.overlaps(&b) && a.to != b.from && b.to != a.from),
+            }
+        }
+
+        #[test]
+        fn prop_merge_all_is_disjoint(periods in prop::collection::vec(period(), 0..10)) {
+            let merged = Period::merge_all(periods.clone());
+            for pair in merged.windows(2) {
+                prop_assert!(pair[0].to < pair[1].from);
+            }
+            for period in periods.iter().filter(|period| !period.is_empty()) {
+                prop_assert!(merged.iter().any(|merged| merged.contains(period)));
+            }
+            let total = merged.iter().map(Period::duration).fold(Duration::zero(), |a, b| a + b);
+            let sum = periods.iter().map(Period::duration).fold(Duration::zero(), |a, b| a + b);
+            prop_assert!(total <= sum);
+        }
+
+        #[test]
+        fn prop_split_days_partitions(a in period()) {
+            let parts = a.split_days();
+            prop_assert_eq!(parts.len(), a.days().count());
+            let total = parts.iter().map(Period::duration).fold(Duration::zero(), |a, b| a + b);
+            prop_assert_eq!(total, a.duration());
+            for (part, day) in parts.iter().zip(a.days()) {
+                prop_assert!(Period::from(day).contains(part));
+                prop_assert!(a.contains(part));
+            }
+            for pair in parts.windows(2) {
+                prop_assert_eq!(pair[0].to, pair[1].from);
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::model::EntryType;
+use crate::model::aircraft::Aircraft;
+
+/// Filters for the roster calendar.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarQuery {
+    pub time_bracket: TimeBracket,
+    /// only list entries of this type, all entries if empty
+    pub event_type: Option<EntryType>,
+}
+
+/// Time window of the calendar.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum TimeBracket {
+    /// from today on, this is what upstream shows by default
+    #[default]
+    Upcoming,
+    /// everything before today
+    Past,
+    /// the whole given year
+    Season(i32),
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("invalid time bracket: {0}")]
+pub struct ParseTimeBracketError(String);
+
+impl fmt::Display for TimeBracket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeBracket::Upcoming => write!(f, "upcoming"),
+            TimeBracket::Past => write!(f, "past"),
+            TimeBracket::Season(year) => write!(f, "{}", year),
+        }
+    }
+}
+
+impl FromStr for TimeBracket {
+    type Err = ParseTimeBracketError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "upcoming" => Ok(TimeBracket::Upcoming),
+            "past" => Ok(TimeBracket::Past),
+            year => year.parse().map(TimeBracket::Season).map_err(|_| ParseTimeBracketError(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for TimeBracket {
+    type Error = ParseTimeBracketError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<TimeBracket> for String {
+    fn from(value: TimeBracket) -> Self {
+        value.to_string()
+    }
+}
+
+impl CalendarQuery {
+    // form fields of list_roster_new.php
+    pub(crate) fn form(&self) -> Vec<(&'static str, String)> {
+        let time_bracket = match self.time_bracket {
+            TimeBracket::Upcoming => "-9".to_string(),
+            TimeBracket::Past => "-1".to_string(),
+            TimeBracket::Season(year) => year.to_string(),
+        };
+        let event_type = match &self.event_type {
+            None => "0",
+            Some(EntryType::FlightInstructor) => "1",
+            Some(EntryType::TowingPilot) => "2",
+            Some(EntryType::WinchOperator) => "3",
+            // upstream can't filter by types we don't know
+            Some(EntryType::Other(_)) => "0",
+        };
+        vec![("timebracket", time_bracket), ("event_type", event_type.to_string())]
+    }
+}
+
+/// Filters for the aircraft reservation list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReservationQuery {
+    /// registration number of the aircraft, all aircraft if empty
+    pub aircraft: Option<String>,
+    #[serde(default)]
+    pub timeframe: ReservationTimeframe,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReservationTimeframe {
+    /// from today on, upstream's default
+    #[default]
+    Upcoming,
+    All,
+}
+
+impl ReservationQuery {
+    pub fn for_aircraft(aircraft: &Aircraft) -> Self {
+        Self {
+            aircraft: Some(aircraft.registration_number.clone()),
+            ..Default::default()
+        }
+    }
+
+    // form fields of reservation_aircraft.php
+    pub(crate) fn form(&self) -> Vec<(&'static str, String)> {
+        let timeframe = match self.timeframe {
+            ReservationTimeframe::Upcoming => "-1",
+            ReservationTimeframe::All => "0",
+        };
+        vec![
+            ("Dacft", self.aircraft.clone().unwrap_or_else(|| "all".to_string())),
+            ("Dtimeframe", timeframe.to_string()),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_bracket_round_trip() {
+        for bracket in [TimeBracket::Upcoming, TimeBracket::Past, TimeBracket::Season(2022)] {
+            assert_eq!(bracket.to_string().parse::<TimeBracket>(), Ok(bracket));
+        }
+        assert!("yesterday".parse::<TimeBracket>().is_err());
+    }
+
+    #[test]
+    fn test_default_forms() {
+        assert_eq!(CalendarQuery::default().form(), vec![("timebracket", "-9".to_string()), ("event_type", "0".to_string())]);
+        assert_eq!(ReservationQuery::default().form(), vec![("Dacft", "all".to_string()), ("Dtimeframe", "-1".to_string())]);
+        let all = ReservationQuery { timeframe: ReservationTimeframe::All, ..Default::default() };
+        assert_eq!(all.form(), vec![("Dacft", "all".to_string()), ("Dtimeframe", "0".to_string())]);
+    }
+}
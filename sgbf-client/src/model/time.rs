@@ -0,0 +1,79 @@
+//! The club's timezone and how timestamps are (de)serialized.
+//!
+//! Upstream only shows wall-clock times, which are always in [`TIMEZONE`]. Timestamps are
+//! serialized as RFC 3339 with their offset. When deserializing, naive timestamps and plain dates
+//! as written by older versions of the API are still accepted and read as club-local time.
+
+use chrono::{LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use chrono_tz::Tz;
+
+/// The timezone all upstream times are given in.
+pub const TIMEZONE: Tz = chrono_tz::Europe::Zurich;
+
+pub type DateTime = chrono::DateTime<Tz>;
+
+/// Interprets a wall-clock time in the club's timezone. Ambiguous times at the end of daylight
+/// saving time resolve to the earlier instant, times skipped at its start are moved an hour later.
+pub fn localize(naive: NaiveDateTime) -> DateTime {
+    match TIMEZONE.from_local_datetime(&naive) {
+        LocalResult::Single(datetime) => datetime,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => localize(naive + chrono::Duration::hours(1)),
+    }
+}
+
+/// Midnight at the start of the given day.
+pub fn start_of_day(date: NaiveDate) -> DateTime {
+    localize(date.and_time(NaiveTime::MIN))
+}
+
+fn parse(value: &str) -> Option<DateTime> {
+    if let Ok(datetime) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&TIMEZONE));
+    }
+    // naive json written before timestamps carried an offset
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(localize(naive));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(start_of_day)
+}
+
+/// Use with `#[serde(with = "crate::model::time::rfc3339")]`.
+pub mod rfc3339 {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use super::DateTime;
+
+    pub fn serialize<S: Serializer>(datetime: &DateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&datetime.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        super::parse(&value)
+            .ok_or_else(|| de::Error::custom(format!("invalid timestamp: {}", value)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let summer = "2023-07-16T11:00:00+02:00";
+        assert_eq!(parse(summer).unwrap().to_rfc3339(), summer);
+        assert_eq!(parse("2023-07-16T09:00:00Z").unwrap().to_rfc3339(), summer);
+        assert_eq!(parse("2023-07-16T11:00:00").unwrap().to_rfc3339(), summer);
+        assert_eq!(parse("2023-01-16").unwrap().to_rfc3339(), "2023-01-16T00:00:00+01:00");
+        assert!(parse("16.07.2023").is_none());
+    }
+
+    #[test]
+    fn test_localize_dst() {
+        let naive = |value| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+        // skipped hour in spring
+        assert_eq!(localize(naive("2023-03-26 02:30")).to_rfc3339(), "2023-03-26T03:30:00+02:00");
+        // repeated hour in autumn
+        assert_eq!(localize(naive("2023-10-29 02:30")).to_rfc3339(), "2023-10-29T02:30:00+02:00");
+    }
+}
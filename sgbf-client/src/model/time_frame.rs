@@ -0,0 +1,151 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use chrono::{Duration, NaiveTime};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::model::{Contains, Overlaps};
+
+/// Wall-clock times in [`crate::model::time::TIMEZONE`] from `start` up to, but not including,
+/// `end`. See [`crate::model::Period::on`] for the actual period on a given day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "TimeFrameRepr")]
+pub struct TimeFrame {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TimeFrameError {
+    #[error("expected \"HH:MM - HH:MM\", got {0:?}")]
+    Format(String),
+    #[error("end {end} is before start {start}")]
+    EndBeforeStart { start: NaiveTime, end: NaiveTime },
+}
+
+// older versions of the API serialised time frames as a `[start, end]` tuple
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TimeFrameRepr {
+    Struct { start: NaiveTime, end: NaiveTime },
+    Tuple(NaiveTime, NaiveTime),
+}
+
+impl TryFrom<TimeFrameRepr> for TimeFrame {
+    type Error = TimeFrameError;
+
+    fn try_from(repr: TimeFrameRepr) -> Result<Self, Self::Error> {
+        match repr {
+            TimeFrameRepr::Struct { start, end } | TimeFrameRepr::Tuple(start, end) => TimeFrame::new(start, end),
+        }
+    }
+}
+
+impl TimeFrame {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Result<Self, TimeFrameError> {
+        if end < start {
+            return Err(TimeFrameError::EndBeforeStart { start, end });
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveTime {
+        self.end
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.end - self.start
+    }
+
+    /// The union of two time frames that overlap or touch, `None` if there is a gap between them.
+    pub fn merge(&self, other: &TimeFrame) -> Option<TimeFrame> {
+        (self.start <= other.end && other.start <= self.end).then(|| TimeFrame {
+            start: self.start.min(other.start),
+            end: self.end.max(other.end),
+        })
+    }
+
+    /// Merges overlapping and touching time frames, e.g. to find the hours covered by a duty.
+    /// The result is sorted and no two time frames in it overlap or touch.
+    pub fn merge_all(time_frames: impl IntoIterator<Item = TimeFrame>) -> Vec<TimeFrame> {
+        let mut time_frames = time_frames.into_iter().collect::<Vec<_>>();
+        time_frames.sort_by_key(|time_frame| time_frame.start);
+        let mut merged: Vec<TimeFrame> = Vec::with_capacity(time_frames.len());
+        for time_frame in time_frames {
+            match merged.last_mut().and_then(|last| last.merge(&time_frame).map(|union| (last, union))) {
+                Some((last, union)) => *last = union,
+                None => merged.push(time_frame),
+            }
+        }
+        merged
+    }
+}
+
+impl Overlaps<TimeFrame> for TimeFrame {
+    fn overlaps(&self, other: &TimeFrame) -> bool {
+        self.start.max(other.start) < self.end.min(other.end)
+    }
+}
+
+impl Contains<NaiveTime> for TimeFrame {
+    fn contains(&self, other: &NaiveTime) -> bool {
+        self.start <= *other && *other < self.end
+    }
+}
+
+impl Display for TimeFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.start.format("%H:%M"), self.end.format("%H:%M"))
+    }
+}
+
+impl FromStr for TimeFrame {
+    type Err = TimeFrameError;
+
+    /// Parses the upstream format, e.g. "09:00 - 13:30".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let format = || TimeFrameError::Format(s.to_string());
+        let (start, end) = s.split_once('-').ok_or_else(format)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| format())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| format())?;
+        TimeFrame::new(start, end)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn frame(s: &str) -> TimeFrame {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(frame("09:00 - 13:30").to_string(), "09:00 - 13:30");
+        assert_eq!(frame("09:00\u{a0}-\u{a0}13:30"), frame("09:00 - 13:30"));
+        assert!(matches!("13:30 - 09:00".parse::<TimeFrame>(), Err(TimeFrameError::EndBeforeStart { .. })));
+        assert!(matches!("9 Uhr".parse::<TimeFrame>(), Err(TimeFrameError::Format(_))));
+    }
+
+    #[test]
+    fn test_serde() {
+        let json = serde_json::to_string(&frame("09:00 - 13:30")).unwrap();
+        assert_eq!(json, r#"{"start":"09:00:00","end":"13:30:00"}"#);
+        assert_eq!(serde_json::from_str::<TimeFrame>(&json).unwrap(), frame("09:00 - 13:30"));
+        assert_eq!(serde_json::from_str::<TimeFrame>(r#"["09:00:00","13:30:00"]"#).unwrap(), frame("09:00 - 13:30"));
+        assert!(serde_json::from_str::<TimeFrame>(r#"["13:30:00","09:00:00"]"#).is_err());
+    }
+
+    #[test]
+    fn test_merge_all() {
+        let merged = TimeFrame::merge_all([frame("13:00 - 17:00"), frame("09:00 - 12:00"), frame("12:00 - 13:30")]);
+        assert_eq!(merged, vec![frame("09:00 - 17:00")]);
+        assert!(!frame("09:00 - 12:00").overlaps(&frame("12:00 - 13:00")));
+        assert_eq!(frame("09:00 - 12:00").merge(&frame("12:30 - 13:00")), None);
+    }
+}
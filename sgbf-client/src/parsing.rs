@@ -1,57 +1,152 @@
 mod calendar;
 mod day;
+mod duty;
 mod menu;
 mod reservation;
 mod members;
+mod table;
+mod error;
+mod fleet;
 
-use anyhow::Context;
+use std::fmt;
 
 use serde::{Deserialize, Serialize};
 use tracing::instrument;
 use scraper::ElementRef;
-use crate::model::{Day, DayOverview, EntryType, PersonEntry, Reservation, TimeFrame};
+use crate::model::{Day, DayOverview, Duty, EntryType, PersonEntry, Reservation, TimeFrame};
+use crate::model::aircraft::FleetAircraft;
+
+pub use error::ParseError;
 
 #[derive(Debug, Default)]
 pub struct Parser {
     day_parser: day::Parser,
+    duty_parser: duty::Parser,
     calendar_parser: calendar::Parser,
     menu_parser: menu::Parser,
     reservation_parser: reservation::Parser,
     member_parser: members::Parser,
+    fleet_parser: fleet::Parser,
 }
 
 impl Parser {
 
     #[instrument(skip(document))]
-    pub fn parse_day(&self, document: String) -> anyhow::Result<Day> {
+    pub fn parse_day(&self, document: String) -> Result<Day, ParseError> {
         let document = scraper::Html::parse_document(&document);
         self.day_parser.parse(&document)
     }
 
     #[instrument(skip(document))]
-    pub fn parse_calendar(&self, document: String) -> anyhow::Result<Vec<DayOverview>> {
+    pub fn parse_duty(&self, document: String) -> Result<Duty, ParseError> {
+        let document = scraper::Html::parse_document(&document);
+        self.duty_parser.parse(&document)
+    }
+
+    #[instrument(skip(document))]
+    pub fn parse_calendar(&self, document: String) -> Result<Vec<DayOverview>, ParseError> {
         let document = scraper::Html::parse_document(&document);
         self.calendar_parser.parse(&document)
     }
 
     #[instrument(skip(document))]
-    pub fn parse_menu(&self, document: String) -> anyhow::Result<String> {
+    pub fn parse_menu(&self, document: String) -> Result<String, ParseError> {
         let document = scraper::Html::parse_document(&document);
         self.menu_parser.parse(&document)
     }
 
     #[instrument(skip(document))]
-    pub fn parse_reservations(&self, document: String) -> anyhow::Result<Vec<Reservation>> {
+    pub fn parse_reservations(&self, document: String) -> Result<Vec<Reservation>, ParseError> {
         let document = scraper::Html::parse_document(&document);
         self.reservation_parser.parse(&document)
     }
 
     #[instrument(skip(document))]
-    pub fn parse_members(&self, document: String) -> anyhow::Result<Vec<crate::model::Member>> {
+    pub fn parse_members(&self, document: String) -> Result<Vec<crate::model::Member>, ParseError> {
         let document = scraper::Html::parse_document(&document);
         self.member_parser.parse(&document)
     }
 
+    #[instrument(skip(document))]
+    pub fn parse_fleet(&self, document: String) -> Result<Vec<FleetAircraft>, ParseError> {
+        let document = scraper::Html::parse_document(&document);
+        self.fleet_parser.parse(&document)
+    }
+
+}
+
+/// The upstream pages the client knows how to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PageKind {
+    Menu,
+    Calendar,
+    Day,
+    Duty,
+    Reservations,
+    Members,
+}
+
+impl fmt::Display for PageKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PageKind::Menu => "menu",
+            PageKind::Calendar => "calendar",
+            PageKind::Day => "day",
+            PageKind::Duty => "duty",
+            PageKind::Reservations => "reservations",
+            PageKind::Members => "members",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What upstream actually served in place of the requested page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageState {
+    Content,
+    LoggedOut,
+    Maintenance,
+}
+
+// upstream replaces every page with a static notice while it is being maintained, announced in
+// its title or a heading. notes and remarks on regular pages may mention maintenance too.
+const MAINTENANCE_MARKERS: [&str; 2] = ["Wartungsarbeiten", "Wartungsmodus"];
+// pages requiring a session render the login form instead
+const LOGIN_MARKER: &str = "login_user";
+// the calendar is rendered without entries for anonymous visitors
+const CALENDAR_LOGGED_OUT_MARKER: &str = "Eintrag vorhanden";
+
+pub fn page_state(page: PageKind, document: &str) -> PageState {
+    if is_maintenance_notice(document) {
+        return PageState::Maintenance;
+    }
+    let logged_out = match page {
+        // the menu is served to anonymous visitors as well, it's how we check a session
+        PageKind::Menu => false,
+        PageKind::Calendar => document.contains(LOGIN_MARKER) || document.contains(CALENDAR_LOGGED_OUT_MARKER),
+        _ => document.contains(LOGIN_MARKER),
+    };
+    if logged_out {
+        PageState::LoggedOut
+    } else {
+        PageState::Content
+    }
+}
+
+/// The notice has none of the tables every page keeps its data in.
+fn is_maintenance_notice(document: &str) -> bool {
+    // most pages never mention maintenance, spare them a second parse
+    if !MAINTENANCE_MARKERS.iter().any(|marker| document.contains(marker)) {
+        return false;
+    }
+    let tables = scraper::Selector::parse("table").unwrap();
+    let headings = scraper::Selector::parse("title, h1, h2, h3").unwrap();
+    let document = scraper::Html::parse_document(document);
+    document.select(&tables).next().is_none() && document.select(&headings).any(|heading| {
+        let text = heading.text().collect::<String>();
+        MAINTENANCE_MARKERS.iter().any(|marker| text.contains(marker))
+    })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -67,11 +162,12 @@ pub struct TableEntry {
 }
 
 impl TryFrom<Vec<TableEntry>> for DayOverview {
-    type Error = anyhow::Error;
+    type Error = ParseError;
 
     #[instrument(skip(value))]
-    fn try_from(value: Vec<TableEntry>) -> anyhow::Result<Self> {
-        let first = value.first().context("couldn't fetch first entry")?;
+    fn try_from(value: Vec<TableEntry>) -> Result<Self, ParseError> {
+        let first = value.first()
+            .ok_or_else(|| ParseError::new(PageKind::Calendar, "day without entries"))?;
         let date = first.date;
         let note = first.day_note.clone();
         let registered_pilots = first.registered_pilots;
@@ -102,3 +198,53 @@ impl TryFrom<Vec<TableEntry>> for DayOverview {
 pub fn get_text(el: ElementRef) -> Vec<String> {
     el.text().map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect::<Vec<_>>()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_malformed_pages_return_errors() {
+        let parser = Parser::default();
+        let documents = [
+            "",
+            "<html><body><p>nothing here</p></body></html>",
+            "<table><tr><td>16.07.2023</td></tr></table>",
+            "<form><input name='T_My_Action'></form>",
+        ];
+        for document in documents {
+            assert!(parser.parse_day(document.to_string()).is_err());
+            assert!(parser.parse_duty(document.to_string()).is_err());
+            assert!(parser.parse_calendar(document.to_string()).is_err());
+            assert!(parser.parse_menu(document.to_string()).is_err());
+            assert!(parser.parse_members(document.to_string()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_maintenance_notice() {
+        let notice = "<html><head><title>Wartungsarbeiten</title></head>\
+            <body><h1>Wartungsarbeiten</h1><p>Bitte versuchen Sie es später.</p></body></html>";
+        assert_eq!(page_state(PageKind::Calendar, notice), PageState::Maintenance);
+        // a regular page whose remarks mention maintenance
+        let calendar = "<html><head><title>Einsatzplanung</title></head><body><table>\
+            <tr><td>Datum</td><td>Bemerkung</td></tr>\
+            <tr><td>16.07.2023</td><td>Wartungsarbeiten an der Winde</td></tr>\
+            </table></body></html>";
+        assert_eq!(page_state(PageKind::Calendar, calendar), PageState::Content);
+        let remark = "<html><body><h2>Hinweis</h2><p>Wartungsarbeiten am Hangar</p></body></html>";
+        assert_eq!(page_state(PageKind::Day, remark), PageState::Content);
+    }
+
+    #[test]
+    fn test_reservation_row_error_has_location() {
+        let document = "<table>\
+            <tr><td><table><tr><td><a name='12'></a>HB-XYZ</td><td>01.07.2023</td><td>Muster</td></tr></table></td></tr>\
+            <tr><td>16.07.2023 11:00</td></tr>\
+            </table>";
+        let error = Parser::default().parse_reservations(document.to_string()).unwrap_err();
+        assert_eq!(error.page, PageKind::Reservations);
+        assert_eq!(error.row, Some(0));
+        assert!(error.snippet.is_some());
+    }
+}
@@ -1,8 +1,9 @@
-use anyhow::{anyhow, Context};
 use scraper::{ElementRef, Html};
-use tracing::instrument;
+use tracing::{instrument, trace};
 use crate::model::{DayOverview, EntryType, TimeFrame};
-use crate::parsing::TableEntry;
+use crate::model::time_frame::TimeFrameError;
+use crate::parsing::{PageKind, ParseError, TableEntry};
+use crate::parsing::table::{Column, ColumnMap};
 
 #[derive(Debug, Default)]
 pub struct Parser {
@@ -13,7 +14,6 @@ pub struct Parser {
 struct Selectors {
     table: scraper::Selector,
     tr: scraper::Selector,
-    td: scraper::Selector,
     a: scraper::Selector,
 }
 
@@ -22,7 +22,6 @@ impl Selectors {
         Self {
             table: scraper::Selector::parse("table").unwrap(),
             tr: scraper::Selector::parse("tr").unwrap(),
-            td: scraper::Selector::parse("td").unwrap(),
             a: scraper::Selector::parse("a").unwrap(),
         }
     }
@@ -34,94 +33,122 @@ impl Default for Selectors {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CalendarColumn {
+    Date,
+    RegisteredPilots,
+    DayNote,
+    FlightNote,
+    TimeFrame,
+    EntryType,
+    Note,
+    Name,
+}
+
+impl Column for CalendarColumn {
+    const REQUIRED: &'static [Self] = &[
+        CalendarColumn::Date,
+        CalendarColumn::RegisteredPilots,
+        CalendarColumn::DayNote,
+        CalendarColumn::FlightNote,
+        CalendarColumn::TimeFrame,
+        CalendarColumn::EntryType,
+        CalendarColumn::Note,
+        CalendarColumn::Name,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            CalendarColumn::Date => "date",
+            CalendarColumn::RegisteredPilots => "registered pilots",
+            CalendarColumn::DayNote => "day note",
+            CalendarColumn::FlightNote => "flight note",
+            CalendarColumn::TimeFrame => "time frame",
+            CalendarColumn::EntryType => "entry type",
+            CalendarColumn::Note => "note",
+            CalendarColumn::Name => "name",
+        }
+    }
+
+    fn labels(&self) -> &'static [&'static str] {
+        match self {
+            CalendarColumn::Date => &["datum"],
+            CalendarColumn::RegisteredPilots => &["sf"],
+            CalendarColumn::DayNote => &["feiertag", "anlass", "ereignis"],
+            CalendarColumn::FlightNote => &["info", "hinweis", "flugbetrieb"],
+            CalendarColumn::TimeFrame => &["zeit"],
+            CalendarColumn::EntryType => &["funktion", "typ"],
+            CalendarColumn::Note => &["bemerkung", "notiz"],
+            CalendarColumn::Name => &["name"],
+        }
+    }
+}
+
 impl Parser {
 
-    pub fn parse(&self, document: &Html) -> anyhow::Result<Vec<DayOverview>> {
+    pub fn parse(&self, document: &Html) -> Result<Vec<DayOverview>, ParseError> {
         // find table element
         let table = document.select(&self.selectors.table).take(1).next()
-            .context("could not select table")?;
+            .ok_or_else(|| ParseError::new(PageKind::Calendar, "could not select table"))?;
 
-        // find table rows
-        let rows = table.select(&self.selectors.tr);
-        // skip the first four rows
-        let rows = rows.skip(4);
-        let rows = rows.filter(|row| row.children().count() > 3).flat_map(|row| self.parse_row(row)).collect::<Vec<_>>();
+        // find the header row and map the columns we need
+        let rows = table.select(&self.selectors.tr).collect::<Vec<_>>();
+        let (columns, rows) = ColumnMap::<CalendarColumn>::find(PageKind::Calendar, rows)?;
+        let mut entries = vec![];
+        for (index, row) in rows.into_iter().enumerate() {
+            // separator rows between weeks and months
+            let Some(cells) = columns.data_cells(PageKind::Calendar, row).map_err(|error| error.at_row(index))? else {
+                trace!("skipping separator row");
+                continue;
+            };
+            entries.push(self.parse_row(&columns, &cells).map_err(|error| error.at_row(index))?);
+        }
 
         // group by day
         let mut grouped_rows: Vec<Vec<TableEntry>> = Vec::new();
-        let mut current_day = None;
-        for row in rows {
-            if let Some(day) = current_day {
-                if day == row.date {
-                    grouped_rows.last_mut()
-                        .context("could not get last row")?
-                        .push(row);
-                } else {
-                    current_day = Some(row.date);
-                    grouped_rows.push(vec![row]);
-                }
-            } else {
-                current_day = Some(row.date);
-                grouped_rows.push(vec![row]);
+        for row in entries {
+            match grouped_rows.last_mut() {
+                Some(day) if day.first().is_some_and(|entry| entry.date == row.date) => day.push(row),
+                _ => grouped_rows.push(vec![row]),
             }
         }
 
         Ok(grouped_rows.into_iter().flat_map(DayOverview::try_from).collect::<Vec<_>>())
     }
 
-    fn parse_row(&self, el: ElementRef) -> anyhow::Result<TableEntry> {
-        let mut tds = el.select(&self.selectors.td);
-        // first td contains date
-        let date = tds.next().map(|td| self.parse_date(td))
-            .ok_or_else(|| anyhow!("coult not get next td"))?
-            .context("could not parse date")?;
-        // skip second (kw)
-        let _ = tds.next();
-        // third td contains count of registered pilots
-        let (reg, pot) = parse_registered_pilots(tds.next().context("could not get next td")?);
-        // skip the next two
+    fn parse_row(&self, columns: &ColumnMap<CalendarColumn>, cells: &[ElementRef]) -> Result<TableEntry, ParseError> {
+        // runs `parse` on the cell of `column`, attaching the cell to any error
+        fn parse_cell<T>(
+            columns: &ColumnMap<CalendarColumn>,
+            cells: &[ElementRef],
+            column: CalendarColumn,
+            parse: impl FnOnce(ElementRef) -> Result<T, String>,
+        ) -> Result<T, ParseError> {
+            let cell = columns.get(cells, column).ok_or_else(|| {
+                ParseError::new(PageKind::Calendar, "missing cell").in_column(column.name())
+            })?;
+            parse(cell).map_err(|message| {
+                ParseError::new(PageKind::Calendar, message).in_column(column.name()).with_snippet(cell)
+            })
+        }
+
+        let date = parse_cell(columns, cells, CalendarColumn::Date, |el| self.parse_date(el))?;
+        // count of registered pilots
+        let (reg, pot) = parse_cell(columns, cells, CalendarColumn::RegisteredPilots, parse_registered_pilots)?;
         // holidays & events
-        let day_note = tds.next()
-            .context("could not get next td")?
-            .text().collect::<String>().trim().to_string();
-        let day_note = if day_note.is_empty() {
-            None
-        } else {
-            Some(day_note)
-        };
+        let day_note = parse_cell(columns, cells, CalendarColumn::DayNote, |el| Ok(text(el)))?;
         // short note for flight information (eg. winch evening or no motorized flights)
-        let note_2 = tds.next()
-            .context("could not get next td")?
-            .text().collect::<String>().trim().to_string();
-        let note_2 = if note_2.is_empty() {
-            None
-        } else {
-            Some(note_2)
-        };
+        let note_1 = parse_cell(columns, cells, CalendarColumn::FlightNote, |el| Ok(text(el)))?;
         // time frame of entry
-        let time_frame = parse_time_frame(tds.next()
-            .context("could not get next td")?
-        );
-        // next is entry type
-        let entry_type = parse_entry_type(tds.next()
-            .context("could not get next td")?
-        );
-        // skip 3
-        let _ = tds.next();
-        let _ = tds.next();
-        // longer note
-        let note = tds.next()
-            .context("could not get next td")?;
-        // if note contains an <a> tag, extract its title
-        let note_3 = note.select(&self.selectors.a).next().and_then(|a| a.value()
-            .attr("title").map(|v| v.to_string()));
-        // next is name of person corresponding to entry type
-        let name = tds.next().context("could not get next td")?.text().collect::<String>().trim().to_string();
-        let name = if name.is_empty() {
-            None
-        } else {
-            Some(name)
-        };
+        let time_frame = parse_cell(columns, cells, CalendarColumn::TimeFrame, parse_time_frame)?;
+        let entry_type = parse_cell(columns, cells, CalendarColumn::EntryType, |el| Ok(parse_entry_type(el)))?;
+        // longer note, if it contains an <a> tag, extract its title
+        let note_2 = parse_cell(columns, cells, CalendarColumn::Note, |el| {
+            Ok(el.select(&self.selectors.a).next()
+                .and_then(|a| a.value().attr("title").map(|v| v.to_string())))
+        })?;
+        // name of person corresponding to entry type
+        let name = parse_cell(columns, cells, CalendarColumn::Name, |el| Ok(text(el)))?;
 
         Ok(TableEntry {
             date,
@@ -130,22 +157,29 @@ impl Parser {
             entry_type,
             name,
             day_note,
-            note_1: note_2,
-            note_2: note_3,
+            note_1,
+            note_2,
         })
     }
 
-    fn parse_date(&self, el: ElementRef) -> anyhow::Result<chrono::NaiveDate> {
-        // select a tags
-        let selected = el.select(&self.selectors.a).nth(1);
-        selected
+    fn parse_date(&self, el: ElementRef) -> Result<chrono::NaiveDate, String> {
+        // the second anchor is named after the date
+        let name = el.select(&self.selectors.a).nth(1)
             .and_then(|el| el.value().attr("name"))
-            .ok_or_else(|| anyhow!("could not get name attribute"))
-            .map(|v| chrono::NaiveDate::parse_from_str(v, "%Y-%m-%d").context("could not parse date"))?
-            .context("could not parse date")
+            .ok_or("could not get name attribute")?;
+        chrono::NaiveDate::parse_from_str(name, "%Y-%m-%d")
+            .map_err(|e| format!("could not parse date {:?}: {}", name, e))
     }
 
+}
 
+fn text(el: ElementRef) -> Option<String> {
+    let text = el.text().collect::<String>().trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
 }
 
 #[instrument(skip(el))]
@@ -156,33 +190,74 @@ fn parse_entry_type(el: ElementRef) -> EntryType {
         "FI" => EntryType::FlightInstructor,
         "S" => EntryType::TowingPilot,
         "W" => EntryType::WinchOperator,
-        _ => panic!("Unknown entry type: {}", text),
+        _ => EntryType::Other(text.to_string()),
     }
 }
 
 #[instrument(skip(el))]
-fn parse_registered_pilots(el: ElementRef) -> (u32, u32) {
+fn parse_registered_pilots(el: ElementRef) -> Result<(u32, u32), String> {
     let text = el.text().collect::<String>();
     let text = text.trim();
     let text = text.replace("&nbsp;", "");
     if text.is_empty() || text == "SF+" {
-        (0, 0)
-    } else {
-        let mut parts = text.split('(');
-        let reg = parts.next().unwrap().trim().parse::<u32>().unwrap();
-        let pot = parts.next().unwrap().trim().strip_suffix(')').unwrap().parse::<u32>().unwrap();
-        (reg, pot)
+        return Ok((0, 0));
     }
+    // "3 (2)": definitive and tentative pilots
+    let (reg, pot) = text.split_once('(')
+        .ok_or_else(|| format!("expected \"registered (tentative)\", got {:?}", text))?;
+    let pot = pot.trim().strip_suffix(')')
+        .ok_or_else(|| format!("missing closing parenthesis in {:?}", text))?;
+    let reg = reg.trim().parse::<u32>().map_err(|e| format!("invalid count {:?}: {}", reg, e))?;
+    let pot = pot.trim().parse::<u32>().map_err(|e| format!("invalid count {:?}: {}", pot, e))?;
+    Ok((reg, pot))
 }
 
 
 #[instrument(skip(el))]
-fn parse_time_frame(el: ElementRef) -> TimeFrame {
+fn parse_time_frame(el: ElementRef) -> Result<TimeFrame, String> {
     let text = el.text().collect::<String>();
     let text = text.trim();
     let text = text.replace("&nbsp;", "");
-    let mut parts = text.split('-');
-    let start = chrono::NaiveTime::parse_from_str(parts.next().unwrap().trim(), "%H:%M").unwrap();
-    let end = chrono::NaiveTime::parse_from_str(parts.next().unwrap().trim(), "%H:%M").unwrap();
-    (start, end)
+    text.parse().map_err(|e: TimeFrameError| e.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::query::CalendarQuery;
+    use crate::model::{EntryType, Stats};
+    use crate::source::{FixtureSource, RosterSource};
+
+    #[tokio::test]
+    async fn test_parse() {
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data"));
+        let days = source.get_calendar(&CalendarQuery::default()).await
+            .expect("tests/data/calendar.html should parse");
+        // week separators are skipped
+        assert_eq!(days.len(), 6);
+        assert_eq!(days.iter().map(|day| day.entries.len()).sum::<usize>(), 13);
+        // every column is found by its label
+        let day = &days[1];
+        assert_eq!(day.date, chrono::NaiveDate::from_ymd_opt(2023, 6, 4).unwrap());
+        assert_eq!(day.registered_pilots, Stats { definitive: 4, tentative: 2 });
+        assert_eq!(day.entries[0].entry_type, EntryType::FlightInstructor);
+        assert_eq!(day.entries[0].note_1.as_deref(), Some("Windenbetrieb"));
+        assert!(day.entries[0].note_2.is_some());
+        assert_eq!(day.entries[1].entry_type, EntryType::WinchOperator);
+        assert_eq!(days[4].note.as_deref(), Some("Fluglager"));
+        assert_eq!(days[5].entries[1].entry_type, EntryType::Other("XS".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_row() {
+        let document = "<table>\
+            <tr><th>Datum</th><th>SF</th><th>Feiertag</th><th>Info</th><th>Zeit</th><th>Funktion</th><th>Bemerkung</th><th>Name</th></tr>\
+            <tr><td colspan=8>KW 23</td></tr>\
+            <tr><td><a name=KW23></a><a name=2023-06-04>So 04.06.</a></td><td>1 (0)</td><td></td><td></td>\
+            <td>später</td><td>FI</td><td></td><td>Muster Hans</td></tr>\
+            </table>";
+        let document = scraper::Html::parse_document(document);
+        let error = super::Parser::default().parse(&document).unwrap_err();
+        assert_eq!(error.row, Some(1));
+        assert_eq!(error.column, Some("time frame"));
+    }
 }
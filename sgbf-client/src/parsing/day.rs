@@ -1,7 +1,10 @@
-use anyhow::{bail, Context};
 use scraper::Html;
 use tracing::{instrument, trace};
 use crate::model::{Day, EditAction, ParticipantType, RosterEntry, RosterEntryType};
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
+
+const PAGE: PageKind = PageKind::Day;
 
 #[derive(Debug, Default)]
 pub struct Parser {
@@ -43,13 +46,13 @@ impl Default for Selectors {
 
 impl Parser {
 
-    pub fn parse(&self, document: &Html) -> anyhow::Result<Day> {
-        let roster = self.parse_roster(document).context("could not parse roster")?;
-        let action = self.parse_action(document).context("could not parse action")?;
+    pub fn parse(&self, document: &Html) -> Result<Day, ParseError> {
+        let roster = self.parse_roster(document)?;
+        let action = self.parse_action(document)?;
         let format = document.select(&self.selectors.format).next()
-            .context("could not select format field")?
+            .page_context(PAGE, "could not select format field")?
             .value().attr("value")
-            .context("could not select value attribute")?
+            .page_context(PAGE, "could not select value attribute")?
             .to_string();
         match action {
             EditAction::Add => Ok(Day {
@@ -64,18 +67,18 @@ impl Parser {
             }),
             EditAction::Edit => {
                 let id = document.select(&self.selectors.edit_id).next()
-                    .context("could not select edit field")?
+                    .page_context(PAGE, "could not select edit field")?
                     .value().attr("value")
-                    .context("could not select value attribute")?
+                    .page_context(PAGE, "could not select value attribute")?
                     .parse::<i32>()
-                    .context("could not parse id")?;
+                    .page_context(PAGE, "could not parse id")?;
                 let remarks = document.select(&self.selectors.remarks).next()
-                    .context("could not select remarks field")?
+                    .page_context(PAGE, "could not select remarks field")?
                     .text().collect::<String>();
                 let checked = document.select(&self.selectors.checked).next()
-                    .context("could not select checked field")?
+                    .page_context(PAGE, "could not select checked field")?
                     .value().attr("value")
-                    .context("could not select value attribute")?
+                    .page_context(PAGE, "could not select value attribute")?
                     .to_string();
                 let entry_type = match checked.as_str() {
                     "1" => Some(RosterEntryType::Tentative),
@@ -98,22 +101,22 @@ impl Parser {
     }
 
     #[instrument(skip(document))]
-    pub fn parse_action(&self, document: &Html) -> anyhow::Result<EditAction> {
+    pub fn parse_action(&self, document: &Html) -> Result<EditAction, ParseError> {
         let action = document.select(&self.selectors.action).next()
-            .context("could not select action field")?;
-        let action = action.value().attr("value").unwrap();
+            .page_context(PAGE, "could not select action field")?;
+        let action = action.value().attr("value").page_context(PAGE, "could not select value attribute")?;
         match action {
             "edit" => Ok(EditAction::Edit),
             "add" => Ok(EditAction::Add),
-            _ => bail!("unknown action: {}", action),
+            _ => Err(ParseError::new(PAGE, format!("unknown action: {}", action))),
         }
     }
 
     #[instrument(skip(document))]
-    pub fn parse_roster(&self, document: &Html) -> anyhow::Result<Vec<RosterEntry>> {
+    pub fn parse_roster(&self, document: &Html) -> Result<Vec<RosterEntry>, ParseError> {
         // find 2nd table element
         let table = document.select(&self.selectors.table).nth(2)
-            .context("could not select table")?;
+            .page_context(PAGE, "could not select table")?;
         // iterate over trs
         let mut rows = table.select(&self.selectors.tr);
         let mut current_entry_type = RosterEntryType::Definite;
@@ -124,7 +127,7 @@ impl Parser {
         // once we reach a row with only one td we know that is the header for the Tentative entries
         // once we again reach a row with only one td we know that is the header for the Unavailable entries
         let mut roster_entries = Vec::new();
-        for row in rows {
+        for (index, row) in rows.enumerate() {
             let tds = row.select(&self.selectors.td);
             let tds = tds.map(|td| td.text().collect::<String>()).collect::<Vec<_>>();
             if tds.len() == 1 {
@@ -141,8 +144,12 @@ impl Parser {
             } else {
                 // entry row
                 trace!("entry row: {:?}", tds);
-                let name = tds[0].clone();
-                let message = tds[2].clone();
+                let [name, _, message, ..] = tds.as_slice() else {
+                    return Err(ParseError::new(PAGE, "expected name and message cells")
+                        .at_row(index + 1)
+                        .with_snippet(row));
+                };
+                let (name, message) = (name.clone(), message.clone());
                 roster_entries.push(RosterEntry {
                     name,
                     message,
@@ -0,0 +1,125 @@
+use scraper::Html;
+use tracing::instrument;
+use crate::model::{Duty, EditAction, ParticipantType, TimeFrame};
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
+
+const PAGE: PageKind = PageKind::Duty;
+
+#[derive(Debug, Default)]
+pub struct Parser {
+    selectors: Selectors,
+}
+
+#[derive(Debug)]
+struct Selectors {
+    action: scraper::Selector,
+    edit_id: scraper::Selector,
+    participant_type: scraper::Selector,
+    format: scraper::Selector,
+    time_from: scraper::Selector,
+    time_to: scraper::Selector,
+    remarks: scraper::Selector,
+}
+
+impl Selectors {
+    fn new() -> Self {
+        Self {
+            action: scraper::Selector::parse("input[name=T_My_Action]").unwrap(),
+            edit_id: scraper::Selector::parse("input[name=Tfe_r]").unwrap(),
+            participant_type: scraper::Selector::parse("input[name=Tfe_t]").unwrap(),
+            format: scraper::Selector::parse("input[name=Tfe_f]").unwrap(),
+            time_from: scraper::Selector::parse("input[name=Tfe_time_from]").unwrap(),
+            time_to: scraper::Selector::parse("input[name=Tfe_time_to]").unwrap(),
+            remarks: scraper::Selector::parse("textarea[name=TAfe_fsn]").unwrap(),
+        }
+    }
+}
+
+impl Default for Selectors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+
+    pub fn parse(&self, document: &Html) -> Result<Duty, ParseError> {
+        let action = match self.value(document, &self.selectors.action).page_context(PAGE, "could not select action field")? {
+            "edit" => EditAction::Edit,
+            "add" => EditAction::Add,
+            action => return Err(ParseError::new(PAGE, format!("unknown action: {}", action))),
+        };
+        let code = self.value(document, &self.selectors.participant_type).page_context(PAGE, "could not select type field")?;
+        let participant_type = ParticipantType::from_code(code)
+            .ok_or_else(|| ParseError::new(PAGE, format!("unknown participant type: {}", code)))?;
+        let format = self.value(document, &self.selectors.format).page_context(PAGE, "could not select format field")?
+            .to_string();
+        if action == EditAction::Add {
+            return Ok(Duty {
+                action,
+                id: None,
+                participant_type,
+                format,
+                time_frame: None,
+                remarks: None,
+            });
+        }
+        let id = self.value(document, &self.selectors.edit_id).page_context(PAGE, "could not select edit field")?
+            .parse::<i32>()
+            .page_context(PAGE, "could not parse id")?;
+        let time_frame = self.parse_time_frame(document)?;
+        let remarks = document.select(&self.selectors.remarks).next()
+            .map(|el| el.text().collect::<String>().trim().to_string())
+            .filter(|remarks| !remarks.is_empty());
+        Ok(Duty {
+            action,
+            id: Some(id),
+            participant_type,
+            format,
+            time_frame,
+            remarks,
+        })
+    }
+
+    #[instrument(skip(self, document))]
+    fn parse_time_frame(&self, document: &Html) -> Result<Option<TimeFrame>, ParseError> {
+        let from = self.value(document, &self.selectors.time_from).map(str::trim).unwrap_or_default();
+        let to = self.value(document, &self.selectors.time_to).map(str::trim).unwrap_or_default();
+        if from.is_empty() || to.is_empty() {
+            return Ok(None);
+        }
+        let from = chrono::NaiveTime::parse_from_str(from, "%H:%M").page_context(PAGE, "could not parse start time")?;
+        let to = chrono::NaiveTime::parse_from_str(to, "%H:%M").page_context(PAGE, "could not parse end time")?;
+        TimeFrame::new(from, to).map(Some).page_context(PAGE, "invalid time frame")
+    }
+
+    fn value<'a>(&self, document: &'a Html, selector: &scraper::Selector) -> Option<&'a str> {
+        document.select(selector).next()?.value().attr("value")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::{EditAction, ParticipantType};
+
+    #[test]
+    fn test_parse_edit() {
+        let document = r#"<html><body><form>
+            <input type="hidden" name="T_My_Action" value="edit">
+            <input type="hidden" name="Tfe_t" value="participant_s">
+            <input type="hidden" name="Tfe_r" value="4711">
+            <input type="hidden" name="Tfe_f" value="text">
+            <input type="text" name="Tfe_time_from" value="09:00">
+            <input type="text" name="Tfe_time_to" value="13:30">
+            <textarea name="TAfe_fsn">nur vormittags</textarea>
+        </form></body></html>"#;
+        let document = scraper::Html::parse_document(document);
+        let duty = super::Parser::default().parse(&document).unwrap();
+        assert_eq!(duty.action, EditAction::Edit);
+        assert_eq!(duty.participant_type, ParticipantType::TowingPilot);
+        assert_eq!(duty.id, Some(4711));
+        assert_eq!(duty.time_frame.unwrap().to_string(), "09:00 - 13:30");
+        assert_eq!(duty.remarks.as_deref(), Some("nur vormittags"));
+    }
+}
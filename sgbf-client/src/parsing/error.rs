@@ -0,0 +1,84 @@
+use std::fmt;
+use scraper::ElementRef;
+use thiserror::Error;
+use crate::parsing::PageKind;
+
+// longest html snippet kept for diagnostics
+const SNIPPET_LENGTH: usize = 160;
+
+/// Describes where and why an upstream page could not be parsed.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub struct ParseError {
+    pub page: PageKind,
+    pub message: String,
+    pub row: Option<usize>,
+    pub column: Option<&'static str>,
+    pub snippet: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(page: PageKind, message: impl Into<String>) -> Self {
+        Self {
+            page,
+            message: message.into(),
+            row: None,
+            column: None,
+            snippet: None,
+        }
+    }
+
+    pub fn at_row(mut self, row: usize) -> Self {
+        self.row = Some(row);
+        self
+    }
+
+    pub fn in_column(mut self, column: &'static str) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Keeps the start of the element's html, with whitespace collapsed.
+    pub fn with_snippet(mut self, el: ElementRef) -> Self {
+        let html = el.html().split_whitespace().collect::<Vec<_>>().join(" ");
+        let snippet = match html.char_indices().nth(SNIPPET_LENGTH) {
+            Some((end, _)) => format!("{}…", &html[..end]),
+            None => html,
+        };
+        self.snippet = Some(snippet);
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to parse {} page", self.page)?;
+        if let Some(row) = self.row {
+            write!(f, " at row {}", row)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, " in column {}", column)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(snippet) = &self.snippet {
+            write!(f, " near `{}`", snippet)?;
+        }
+        Ok(())
+    }
+}
+
+/// Turns missing values and foreign errors into a [`ParseError`] for the given page.
+pub(crate) trait ParseContext<T> {
+    fn page_context(self, page: PageKind, message: &str) -> Result<T, ParseError>;
+}
+
+impl<T> ParseContext<T> for Option<T> {
+    fn page_context(self, page: PageKind, message: &str) -> Result<T, ParseError> {
+        self.ok_or_else(|| ParseError::new(page, message))
+    }
+}
+
+impl<T, E: fmt::Display> ParseContext<T> for Result<T, E> {
+    fn page_context(self, page: PageKind, message: &str) -> Result<T, ParseError> {
+        self.map_err(|e| ParseError::new(page, format!("{}: {}", message, e)))
+    }
+}
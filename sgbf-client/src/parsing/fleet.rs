@@ -0,0 +1,83 @@
+use scraper::Html;
+use tracing::{instrument, warn};
+use crate::model::aircraft::{Aircraft, FleetAircraft};
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
+
+#[derive(Debug, Default)]
+pub struct Parser {
+    selectors: Selectors,
+}
+
+#[derive(Debug)]
+struct Selectors {
+    aircraft: scraper::Selector,
+    option: scraper::Selector,
+}
+
+impl Selectors {
+    fn new() -> Self {
+        Self {
+            aircraft: scraper::Selector::parse("select[name=Dacft]").unwrap(),
+            option: scraper::Selector::parse("option").unwrap(),
+        }
+    }
+}
+
+impl Default for Selectors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    /// Reads the aircraft dropdown of the reservation page.
+    #[instrument(skip(self, document))]
+    pub fn parse(&self, document: &Html) -> Result<Vec<FleetAircraft>, ParseError> {
+        let select = document.select(&self.selectors.aircraft).next()
+            .page_context(PageKind::Reservations, "could not select aircraft field")?;
+        let mut fleet = vec![];
+        for option in select.select(&self.selectors.option) {
+            // "all" and other filter entries carry no registration
+            let Some(value) = option.value().attr("value").filter(|value| value.contains('-')) else {
+                continue;
+            };
+            let text = option.text().collect::<String>();
+            let aircraft = match Aircraft::try_from(text.as_str()) {
+                Ok(aircraft) => aircraft,
+                Err(_) => {
+                    warn!(value, text, "skipping unrecognised aircraft");
+                    continue;
+                }
+            };
+            let reservable = option.value().attr("disabled").is_none();
+            fleet.push(FleetAircraft::new(aircraft, reservable));
+        }
+        Ok(fleet)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::aircraft::AircraftClass;
+
+    #[test]
+    fn test_parse() {
+        let document = r#"<html><body><form><select name="Dacft">
+            <option value="all">alle Flugzeuge</option>
+            <option value="HB-3187">HB-3187 LS4 - 9F</option>
+            <option value="HB-2505">HB-2505 DG1001M - FB</option>
+            <option value="HB-3472" disabled>HB-3472 DG1001C- BF2</option>
+        </select></form></body></html>"#;
+        let document = scraper::Html::parse_document(document);
+        let fleet = super::Parser::default().parse(&document).unwrap();
+        let summary = fleet.iter()
+            .map(|aircraft| (aircraft.aircraft.registration_number.as_str(), aircraft.class, aircraft.seats, aircraft.reservable))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            ("HB-3187", AircraftClass::SingleSeater, Some(1), true),
+            ("HB-2505", AircraftClass::MotorGlider, Some(2), true),
+            ("HB-3472", AircraftClass::TwoSeater, Some(2), false),
+        ]);
+    }
+}
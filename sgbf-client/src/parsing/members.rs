@@ -1,8 +1,8 @@
-use std::fmt::Display;
-use itertools::Itertools;
 use scraper::{ElementRef, Html};
-use crate::model::{Addresses, Member, Period, Reservation};
-use crate::model::EditAction::Add;
+use crate::model::{Address, Addresses, Member};
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
+use crate::parsing::table::{Column, ColumnMap};
 
 #[derive(Debug, Default)]
 pub struct Parser {
@@ -13,7 +13,6 @@ pub struct Parser {
 struct Selectors {
     table: scraper::Selector,
     tr: scraper::Selector,
-    td: scraper::Selector,
     a: scraper::Selector,
 }
 
@@ -22,7 +21,6 @@ impl Selectors {
         Self {
             table: scraper::Selector::parse("table").unwrap(),
             tr: scraper::Selector::parse("tr").unwrap(),
-            td: scraper::Selector::parse("td").unwrap(),
             a: scraper::Selector::parse("a").unwrap(),
         }
     }
@@ -34,51 +32,222 @@ impl Default for Selectors {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MemberColumn {
+    Name,
+    Address,
+    Phone,
+    Fax,
+    Mobile,
+    Email,
+    Role,
+}
+
+impl Column for MemberColumn {
+    const REQUIRED: &'static [Self] = &[
+        MemberColumn::Name,
+        MemberColumn::Address,
+        MemberColumn::Phone,
+        MemberColumn::Fax,
+        MemberColumn::Mobile,
+        MemberColumn::Email,
+    ];
+    const OPTIONAL: &'static [Self] = &[MemberColumn::Role];
+
+    fn name(&self) -> &'static str {
+        match self {
+            MemberColumn::Name => "name",
+            MemberColumn::Address => "address",
+            MemberColumn::Phone => "phone",
+            MemberColumn::Fax => "fax",
+            MemberColumn::Mobile => "mobile",
+            MemberColumn::Email => "email",
+            MemberColumn::Role => "role",
+        }
+    }
+
+    fn labels(&self) -> &'static [&'static str] {
+        match self {
+            MemberColumn::Name => &["name"],
+            MemberColumn::Address => &["adresse"],
+            MemberColumn::Phone => &["telefon", "tel."],
+            MemberColumn::Fax => &["fax"],
+            MemberColumn::Mobile => &["mobil", "natel", "handy"],
+            MemberColumn::Email => &["e-mail", "email", "mail"],
+            MemberColumn::Role => &["kategorie", "funktion", "status", "mitglied"],
+        }
+    }
+}
+
 impl Parser {
-    pub fn parse(&self, document: &Html) -> anyhow::Result<Vec<Member>> {
-        let table = document.select(&self.selectors.table).next().unwrap();
-        let rows = table.select(&self.selectors.tr).skip(18).chunks(12);
-
-        let mut members = vec![];
-        for mut row in &rows {
-            let row = row.next().unwrap();
-            let tds = row.select(&self.selectors.td).collect::<Vec<_>>();
-            let name = super::get_text(tds[0]).join("");
-            let address = super::get_text(tds[2]).join("\n");
-            let phone = super::get_text(tds[5]);
-            let fax = super::get_text(tds[8]);
-            let mobile = super::get_text(tds[11]);
-            let mail = super::get_text(tds[14]);
-            let member = Member {
-                name,
-                address: Some(address),
-                private: Addresses {
-                    phone: phone.get(0).map(|s| s.to_string()),
-                    email: mail.get(0).map(|s| s.to_string()),
-                    mobile: mobile.get(0).map(|s| s.to_string()),
-                },
-                office: Addresses {
-                    phone: fax.get(1).map(|s| s.to_string()),
-                    email: mail.get(1).map(|s| s.to_string()),
-                    mobile: mobile.get(1).map(|s| s.to_string()),
-                },
+    pub fn parse(&self, document: &Html) -> Result<Vec<Member>, ParseError> {
+        let table = document.select(&self.selectors.table).next()
+            .page_context(PageKind::Members, "could not select table")?;
+        let rows = table.select(&self.selectors.tr).collect::<Vec<_>>();
+        let (columns, rows) = ColumnMap::<MemberColumn>::find(PageKind::Members, rows)?;
+
+        let mut members: Vec<Member> = vec![];
+        for (index, row) in rows.into_iter().enumerate() {
+            // each member is followed by spacer rows
+            let Some(tds) = columns.data_cells(PageKind::Members, row).map_err(|error| error.at_row(index))? else {
+                continue;
             };
-            members.push(member);
+            let cell = |column: MemberColumn| columns.get(&tds, column)
+                .map(super::get_text)
+                .unwrap_or_default();
+            let name = cell(MemberColumn::Name).join(" ");
+            if name.is_empty() {
+                let error = ParseError::new(PageKind::Members, "missing name").in_column(MemberColumn::Name.name());
+                return Err(error.at_row(index).with_snippet(row));
+            }
+            // contact cells hold the private line first and the office line second
+            let contacts = |line: usize| {
+                let value = |column: MemberColumn| cell(column).get(line).cloned();
+                Addresses {
+                    phone: value(MemberColumn::Phone),
+                    fax: value(MemberColumn::Fax),
+                    email: value(MemberColumn::Email),
+                    mobile: value(MemberColumn::Mobile),
+                }
+            };
+            let id = columns.get(&tds, MemberColumn::Name)
+                .and_then(|name| self.linked_id(name))
+                .unwrap_or_else(|| unique_slug(&name, &members));
+            let role = Some(cell(MemberColumn::Role).join(", ")).filter(|role| !role.is_empty());
+            members.push(Member {
+                id,
+                address: parse_address(&cell(MemberColumn::Address)),
+                name,
+                private: contacts(0),
+                office: contacts(1),
+                role,
+            });
         }
 
         Ok(members)
     }
+
+    // e.g. <a href="member_detail.php?id=123">
+    fn linked_id(&self, name: ElementRef) -> Option<String> {
+        name.select(&self.selectors.a)
+            .filter_map(|a| a.value().attr("href"))
+            .filter_map(|href| href.split_once('?').map(|(_, query)| query))
+            .flat_map(|query| query.split('&'))
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, value)| ID_PARAMETERS.contains(key) && !value.is_empty())
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+// query parameters upstream uses for member ids in links
+const ID_PARAMETERS: &[&str] = &["id", "mid", "member_id", "user_id"];
+
+/// Lower case ascii name, with a counter appended if another member has the same name.
+fn unique_slug(name: &str, members: &[Member]) -> String {
+    let slug = name.chars()
+        .map(|c| match c {
+            'ä' | 'Ä' => "ae".to_string(),
+            'ö' | 'Ö' => "oe".to_string(),
+            'ü' | 'Ü' => "ue".to_string(),
+            c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase().to_string(),
+            _ => " ".to_string(),
+        })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-");
+    let taken = |candidate: &str| members.iter().any(|member| member.id == candidate);
+    (1..)
+        .map(|n| if n == 1 { slug.clone() } else { format!("{}-{}", slug, n) })
+        .find(|candidate| !taken(candidate))
+        .unwrap_or(slug)
+}
+
+/// Splits address lines into street and "postcode city", e.g. "8000 Zürich" or "CH-8000 Zürich".
+fn parse_address(lines: &[String]) -> Option<Address> {
+    if lines.is_empty() {
+        return None;
+    }
+    let mut address = Address::default();
+    let mut street = vec![];
+    for line in lines {
+        let place = line.split_once(' ')
+            .map(|(code, city)| (code.rsplit('-').next().unwrap_or(code), city.trim()))
+            .filter(|(code, city)| (4..=5).contains(&code.len()) && code.chars().all(|c| c.is_ascii_digit()) && !city.is_empty());
+        match place {
+            Some((postcode, city)) if address.postcode.is_none() => {
+                address.postcode = Some(postcode.to_string());
+                address.city = Some(city.to_string());
+            }
+            _ => street.push(line.as_str()),
+        }
+    }
+    address.street = Some(street.join(", ")).filter(|street| !street.is_empty());
+    Some(address)
 }
 
 #[cfg(test)]
 mod test {
+    use crate::model::Address;
+    use crate::source::{FixtureSource, RosterSource};
+
+    #[tokio::test]
+    async fn test_parse() {
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data"));
+        let members = source.get_members().await.expect("tests/data/members.html should parse");
+        assert_eq!(members.len(), 8);
+        // every column is found by its label
+        let first = &members[0];
+        assert_eq!(first.id, "12");
+        assert!(first.address.as_ref().is_some_and(|address| address.postcode.is_some()));
+        assert!(first.private.phone.is_some() && first.office.phone.is_some());
+        assert!(first.private.mobile.is_some());
+        assert!(first.private.email.is_some() && first.office.email.is_some());
+        assert_eq!(first.role.as_deref(), Some("Aktiv, Fluglehrer"));
+        assert!(members[1].private.fax.is_some());
+    }
+
+    #[test]
+    fn test_narrow_row() {
+        let document = "<table>\
+            <tr><th>Name</th><th>Adresse</th><th>Telefon</th><th>Fax</th><th>Mobil</th><th>E-Mail</th></tr>\
+            <tr><td>Muster Hans</td><td>Seeweg 12</td><td>044 111 11 11</td></tr>\
+            </table>";
+        let document = scraper::Html::parse_document(document);
+        let error = super::Parser::default().parse(&document).unwrap_err();
+        assert_eq!(error.row, Some(0));
+        assert!(error.message.starts_with("unexpected layout"));
+    }
+
     #[test]
-    fn test_parse() {
-        // tests/data/reservations.html
-        let document = include_str!("../../tests/data/members.html");
+    fn test_parse_contacts() {
+        let document = "<table>\
+            <tr><th>Name</th><th>Adresse</th><th>Telefon</th><th>Fax</th><th>Mobil</th><th>E-Mail</th><th>Kategorie</th></tr>\
+            <tr><td><a href=\"member.php?id=42\">Muster Hans</a></td><td>Seeweg 12<br>CH-8000 Zürich</td>\
+            <td>044 111 11 11<br>044 222 22 22</td><td>044 333 33 33<br>044 444 44 44</td>\
+            <td>079 555 55 55</td><td>hans@example.com<br>hans@work.example.com</td><td>Aktiv</td></tr>\
+            <tr><td colspan=7></td></tr>\
+            <tr><td>Müller Anna</td><td></td><td></td><td></td><td></td><td></td><td></td></tr>\
+            </table>";
         let document = scraper::Html::parse_document(document);
-        let parser = super::Parser::default();
-        let result = parser.parse(&document);
-        assert!(result.is_ok());
+        let members = super::Parser::default().parse(&document).unwrap();
+        assert_eq!(members.len(), 2);
+        let hans = &members[0];
+        assert_eq!(hans.id, "42");
+        assert_eq!(hans.address, Some(Address {
+            street: Some("Seeweg 12".to_string()),
+            postcode: Some("8000".to_string()),
+            city: Some("Zürich".to_string()),
+        }));
+        assert_eq!(hans.private.phone.as_deref(), Some("044 111 11 11"));
+        assert_eq!(hans.private.fax.as_deref(), Some("044 333 33 33"));
+        assert_eq!(hans.office.phone.as_deref(), Some("044 222 22 22"));
+        assert_eq!(hans.office.fax.as_deref(), Some("044 444 44 44"));
+        assert_eq!(hans.office.email.as_deref(), Some("hans@work.example.com"));
+        assert_eq!(hans.office.mobile, None);
+        assert_eq!(hans.role.as_deref(), Some("Aktiv"));
+        let anna = &members[1];
+        assert_eq!(anna.id, "mueller-anna");
+        assert_eq!(anna.address, None);
     }
 }
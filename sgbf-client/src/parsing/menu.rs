@@ -1,5 +1,6 @@
-use anyhow::Context;
 use scraper::Html;
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
 
 
 #[derive(Debug, Default)]
@@ -28,9 +29,9 @@ impl Default for Selectors {
 
 impl Parser {
 
-    pub fn parse(&self, document: &Html) -> anyhow::Result<String> {
+    pub fn parse(&self, document: &Html) -> Result<String, ParseError> {
         let username = document.select(&self.selectors.username).next()
-            .context("could not select username")?
+            .page_context(PageKind::Menu, "could not select username")?
             .text().collect::<String>().trim().to_string();
         Ok(username)
     }
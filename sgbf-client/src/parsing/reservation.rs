@@ -1,10 +1,13 @@
-use std::fmt::Display;
-use itertools::Itertools;
 use scraper::{ElementRef, Html};
 use crate::model::{Period, Reservation};
 use crate::model::aircraft::Aircraft;
+use crate::model::time;
 use crate::parsing;
-use anyhow::Context;
+use crate::parsing::{PageKind, ParseError};
+use crate::parsing::error::ParseContext;
+use crate::parsing::table;
+
+const PAGE: PageKind = PageKind::Reservations;
 
 #[derive(Debug, Default)]
 pub struct Parser {
@@ -14,8 +17,6 @@ pub struct Parser {
 #[derive(Debug)]
 struct Selectors {
     table: scraper::Selector,
-    tr: scraper::Selector,
-    td: scraper::Selector,
     a: scraper::Selector,
 }
 
@@ -23,8 +24,6 @@ impl Selectors {
     fn new() -> Self {
         Self {
             table: scraper::Selector::parse("table").unwrap(),
-            tr: scraper::Selector::parse("tr").unwrap(),
-            td: scraper::Selector::parse("td").unwrap(),
             a: scraper::Selector::parse("a").unwrap(),
         }
     }
@@ -37,82 +36,103 @@ impl Default for Selectors {
 }
 
 impl Parser {
-    pub fn parse(&self, document: &Html) -> anyhow::Result<Vec<Reservation>> {
+    pub fn parse(&self, document: &Html) -> Result<Vec<Reservation>, ParseError> {
         // first table
-        let table = document.select(&self.selectors.table).next().unwrap();
-        // println!("table: {:?}", table.text().collect::<Vec<_>>());
-        // iterate over children
-        let rows = table.select(&self.selectors.tr).skip(2);
-        // iterate over rows in groups of three
-        let rows = rows.chunks(5);
+        let table = document.select(&self.selectors.table).next()
+            .page_context(PAGE, "could not select table")?;
+        // every reservation starts with a row holding a nested table with the plane, the date
+        // when the reservation was made and who made it, followed by rows with the period
+        // and comments until the next reservation
+        let rows = table.children()
+            .filter_map(ElementRef::wrap)
+            .flat_map(|el| match el.value().name() {
+                "tr" => vec![el],
+                // rows may be wrapped in tbody
+                _ => el.children().filter_map(ElementRef::wrap).filter(|el| el.value().name() == "tr").collect(),
+            })
+            .collect::<Vec<_>>();
+        let starts = rows.iter().enumerate()
+            .filter(|(_, row)| row.select(&self.selectors.table).next().is_some())
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        if starts.is_empty() && rows.iter().any(|row| !parsing::get_text(*row).is_empty()) {
+            return Err(table::layout_error(PAGE, &["plane"]));
+        }
 
         let mut reservations = vec![];
-        for chunk in &rows {
-            if let Some((plane, _, _, info, _)) = chunk.collect_tuple() {
-                // only rows with nested table
-                let table = plane.select(&self.selectors.table).next().unwrap();
-                // plane information, date when reservation was made, who made the reservation
-                // a[name] contains the id
-                let id = table.select(&self.selectors.a).next().unwrap();
-                let id = id.value().attr("name").unwrap();
-                let plane_data = parsing::get_text(table);
-                let (plane, reservation_date, who) = (plane_data[0].clone(), plane_data[1].clone(), plane_data[2].clone());
-                let reservation_date = chrono::NaiveDate::parse_from_str(&reservation_date, "%d.%m.%Y").unwrap();
-                let info = parsing::get_text(info);
-                let (period, comments) = if info.len() == 1 {
-                    // single day, no comments (period)
-                    (parse_single_day(&info[0])?, vec![])
-                } else if info.len() == 2 {
-                    // either single day with comments or multiple days without comments
-                    if let Ok(period) = parse_multiple_days(&info[0], &info[1]) {
-                        // multiple days without comments
-                        (period, vec![])
-                    } else {
-                        // single day with comments
-                        (parse_single_day(&info[0])?, info[1..].to_vec())
-                    }
-                } else {
-                    // either single day with comments or multiple days with comments
-                    if let Ok(period) = parse_multiple_days(&info[0], &info[1]) {
-                        // multiple days with comments
-                        (period, info[2..].to_vec())
-                    } else {
-                        // single day with comments
-                        (parse_single_day(&info[0])?, info[1..].to_vec())
-                    }
-                };
-                let reservation = Reservation {
-                    id: id.parse().context(format!("could not parse id: {}", id))?,
-                    plane: Aircraft::try_from(plane.as_str()).context(format!("could not parse plane: {}", plane))?,
-                    created_at: reservation_date,
-                    reserved_by: who,
-                    period,
-                    comments,
-                };
-                reservations.push(reservation);
-            } else {
-                println!("truncating error");
-            }
+        for (n, start) in starts.iter().enumerate() {
+            let end = starts.get(n + 1).copied().unwrap_or(rows.len());
+            let plane = rows[*start];
+            let info = rows[start + 1..end].iter()
+                .map(|row| parsing::get_text(*row))
+                .find(|text| text.first().is_some_and(|first| starts_with_date(first)))
+                .ok_or_else(|| table::layout_error(PAGE, &["period"]).at_row(*start).with_snippet(plane))?;
+            let reservation = self.parse_reservation(plane, info)
+                .map_err(|message| ParseError::new(PAGE, message).at_row(*start).with_snippet(plane))?;
+            reservations.push(reservation);
         }
         Ok(reservations)
     }
 
+    // errors are reported as plain messages, `parse` adds the row they occurred in
+    fn parse_reservation(&self, plane: ElementRef, info: Vec<String>) -> Result<Reservation, String> {
+        let table = plane.select(&self.selectors.table).next()
+            .ok_or("could not select plane table")?;
+        // a[name] contains the id
+        let id = table.select(&self.selectors.a).next()
+            .and_then(|a| a.value().attr("name"))
+            .ok_or("could not select reservation id")?;
+        let plane_data = parsing::get_text(table);
+        let [plane, reservation_date, who] = plane_data.get(..3)
+            .and_then(|data| <[String; 3]>::try_from(data.to_vec()).ok())
+            .ok_or_else(|| format!("unexpected plane data: {:?}", plane_data))?;
+        let reservation_date = chrono::NaiveDate::parse_from_str(&reservation_date, "%d.%m.%Y")
+            .map_err(|_| format!("could not parse reservation date: {}", reservation_date))?;
+        let (period, comments) = if info.len() == 1 {
+            // single day, no comments (period)
+            (parse_single_day(&info[0])?, vec![])
+        } else if let Ok(period) = parse_multiple_days(&info[0], &info[1]) {
+            // multiple days with or without comments
+            (period, info[2..].to_vec())
+        } else {
+            // single day with comments
+            (parse_single_day(&info[0])?, info[1..].to_vec())
+        };
+        Ok(Reservation {
+            id: id.parse().map_err(|_| format!("could not parse id: {}", id))?,
+            plane: Aircraft::try_from(plane.as_str()).map_err(|_| format!("could not parse plane: {}", plane))?,
+            created_at: time::start_of_day(reservation_date),
+            reserved_by: who,
+            period,
+            comments,
+        })
+    }
+
+}
+
+fn starts_with_date(value: &str) -> bool {
+    value.split_whitespace().next()
+        .is_some_and(|date| chrono::NaiveDate::parse_from_str(date, "%d.%m.%Y").is_ok())
 }
 
 // parse single day with format "16.07.2023\u{a0}\u{a0}11:00\u{a0}-\u{a0}18:00"
-fn parse_single_day(value: &str) -> anyhow::Result<Period> {
-    let mut parts = value.split_whitespace();
-    let (date, from, _, to) = (parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap(), parts.next().unwrap());
+fn parse_single_day(value: &str) -> Result<Period, String> {
+    let parts = value.split_whitespace().collect::<Vec<_>>();
+    let [date, from, _, to] = parts.as_slice() else {
+        return Err(format!("unexpected period: {}", value));
+    };
     let from = format!("{} {}", date, from);
     let to = format!("{} {}", date, to);
-    let from = chrono::NaiveDateTime::parse_from_str(&from, "%d.%m.%Y %H:%M")?;
-    let to = chrono::NaiveDateTime::parse_from_str(&to, "%d.%m.%Y %H:%M")?;
-    Ok(Period { from, to })
+    let from = chrono::NaiveDateTime::parse_from_str(&from, "%d.%m.%Y %H:%M")
+        .map_err(|_| format!("could not parse period start: {}", from))?;
+    let to = chrono::NaiveDateTime::parse_from_str(&to, "%d.%m.%Y %H:%M")
+        .map_err(|_| format!("could not parse period end: {}", to))?;
+    Ok(Period { from: time::localize(from), to: time::localize(to) })
 }
 
 // from format: 17.07.2023\u{a0}\u{a0}09:00\u{a0}-
 // to format: 21.07.2023\u{a0}\u{a0}20:00
-fn parse_multiple_days(from: &str, to: &str) -> anyhow::Result<Period> {
+fn parse_multiple_days(from: &str, to: &str) -> Result<Period, chrono::ParseError> {
     // sanitize
     let from = from.trim_end_matches('-');
     // whitespace
@@ -120,18 +140,21 @@ fn parse_multiple_days(from: &str, to: &str) -> anyhow::Result<Period> {
     let to = to.split_whitespace().collect::<Vec<_>>().join(" ");
     let from = chrono::NaiveDateTime::parse_from_str(from.as_str(), "%d.%m.%Y %H:%M")?;
     let to = chrono::NaiveDateTime::parse_from_str(to.as_str(), "%d.%m.%Y %H:%M")?;
-    Ok(Period { from, to })
+    Ok(Period { from: time::localize(from), to: time::localize(to) })
 }
 
 #[cfg(test)]
 mod test {
-    #[test]
-    fn test_parse() {
-        // tests/data/reservations.html
-        let document = include_str!("../../tests/data/reservations.html");
-        let document = scraper::Html::parse_document(document);
-        let parser = super::Parser::default();
-        let result = parser.parse(&document);
-        assert!(result.is_ok());
+    use crate::model::query::ReservationQuery;
+    use crate::source::{FixtureSource, RosterSource};
+
+    #[tokio::test]
+    async fn test_parse() {
+        let source = FixtureSource::new(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data"));
+        let reservations = source.get_reservations(&ReservationQuery::default()).await
+            .expect("tests/data/reservations.html should parse");
+        assert_eq!(reservations.len(), 4);
+        // spans two days and carries a comment
+        assert_eq!(reservations[1].comments.len(), 1);
     }
 }
\ No newline at end of file
@@ -0,0 +1,191 @@
+use std::fmt::Debug;
+use itertools::Itertools;
+use scraper::ElementRef;
+use crate::parsing::{PageKind, ParseError};
+
+/// A column a parser needs, identified by the labels of its header cell.
+pub(crate) trait Column: Copy + Eq + Debug + 'static {
+    const REQUIRED: &'static [Self];
+    /// Columns that are read if present, without being required.
+    const OPTIONAL: &'static [Self] = &[];
+
+    fn name(&self) -> &'static str;
+
+    /// lower case prefixes of the header cell text
+    fn labels(&self) -> &'static [&'static str];
+}
+
+/// Positions of the required and present optional columns, read from the header row of a table.
+#[derive(Debug)]
+pub(crate) struct ColumnMap<C> {
+    columns: Vec<(C, usize)>,
+    width: usize,
+}
+
+impl<C: Column> ColumnMap<C> {
+
+    /// Searches `rows` for the header row and returns the column map together with the rows
+    /// following the header.
+    pub fn find<'a>(page: PageKind, rows: Vec<ElementRef<'a>>) -> Result<(Self, Vec<ElementRef<'a>>), ParseError> {
+        let mut missing = C::REQUIRED.iter().map(|column| column.name()).collect::<Vec<_>>();
+        for (index, row) in rows.iter().enumerate() {
+            let header = cells(*row).into_iter()
+                .map(|cell| cell.text().collect::<String>().trim().to_lowercase())
+                .collect::<Vec<_>>();
+            match Self::from_header(&header) {
+                Ok(map) => return Ok((map, rows[index + 1..].to_vec())),
+                Err(row_missing) => if row_missing.len() < missing.len() {
+                    missing = row_missing;
+                },
+            }
+        }
+        Err(layout_error(page, &missing))
+    }
+
+    fn from_header(header: &[String]) -> Result<Self, Vec<&'static str>> {
+        let mut columns = Vec::new();
+        let mut missing = Vec::new();
+        for column in C::REQUIRED {
+            match Self::position(header, column) {
+                Some(index) => columns.push((*column, index)),
+                None => missing.push(column.name()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+        let width = columns.iter().map(|(_, index)| index + 1).max().unwrap_or_default();
+        for column in C::OPTIONAL {
+            if let Some(index) = Self::position(header, column) {
+                columns.push((*column, index));
+            }
+        }
+        Ok(Self { columns, width })
+    }
+
+    fn position(header: &[String], column: &C) -> Option<usize> {
+        header.iter().position(|cell| {
+            column.labels().iter().any(|label| cell.starts_with(label))
+        })
+    }
+
+    /// Whether the row has a cell for every required column.
+    pub fn spans(&self, cells: &[ElementRef]) -> bool {
+        cells.len() >= self.width
+    }
+
+    /// The cells of a data row, `None` for separators, i.e. rows with only empty cells or a single
+    /// cell spanning the table. Any other row not spanning the table is an unexpected layout.
+    pub fn data_cells<'a>(&self, page: PageKind, row: ElementRef<'a>) -> Result<Option<Vec<ElementRef<'a>>>, ParseError> {
+        let cells = cells(row);
+        let single = cells.iter().map(|cell| cell.id()).dedup().count() <= 1;
+        let empty = cells.iter().all(|cell| cell.text().all(|text| text.trim().is_empty()));
+        let spans = self.spans(&cells);
+        if empty || (single && spans) {
+            return Ok(None);
+        }
+        if !spans {
+            let message = format!("unexpected layout, row has {} of {} cells", cells.len(), self.width);
+            return Err(ParseError::new(page, message).with_snippet(row));
+        }
+        Ok(Some(cells))
+    }
+
+    pub fn get<'a>(&self, cells: &[ElementRef<'a>], column: C) -> Option<ElementRef<'a>> {
+        self.columns.iter()
+            .find(|(c, _)| *c == column)
+            .and_then(|(_, index)| cells.get(*index).copied())
+    }
+}
+
+pub(crate) fn layout_error(page: PageKind, missing: &[&str]) -> ParseError {
+    ParseError::new(page, format!("unexpected layout, missing columns: {}", missing.join(", ")))
+}
+
+/// The direct `td`/`th` children of a row, repeated according to their `colspan` so that
+/// indices line up with the header.
+pub(crate) fn cells(row: ElementRef) -> Vec<ElementRef> {
+    row.children()
+        .filter_map(ElementRef::wrap)
+        .filter(|el| matches!(el.value().name(), "td" | "th"))
+        .flat_map(|el| {
+            let span = el.value().attr("colspan")
+                .and_then(|span| span.parse::<usize>().ok())
+                .unwrap_or(1)
+                .max(1);
+            std::iter::repeat_n(el, span)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use scraper::{Html, Selector};
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TestColumn {
+        Date,
+        Name,
+    }
+
+    impl Column for TestColumn {
+        const REQUIRED: &'static [Self] = &[TestColumn::Date, TestColumn::Name];
+
+        fn name(&self) -> &'static str {
+            match self {
+                TestColumn::Date => "date",
+                TestColumn::Name => "name",
+            }
+        }
+
+        fn labels(&self) -> &'static [&'static str] {
+            match self {
+                TestColumn::Date => &["datum"],
+                TestColumn::Name => &["name"],
+            }
+        }
+    }
+
+    fn document(html: &str) -> Html {
+        Html::parse_document(html)
+    }
+
+    #[test]
+    fn test_find_header_with_colspan() {
+        let document = document("<table><tr><td colspan=3>Kalender</td></tr>\
+            <tr><th colspan=2>Datum</th><th>Name</th></tr>\
+            <tr><td>KW</td><td>2023-06-04</td><td>Muster</td></tr></table>");
+        let rows = document.select(&Selector::parse("tr").unwrap()).collect::<Vec<_>>();
+        let (columns, data) = ColumnMap::<TestColumn>::find(PageKind::Calendar, rows).unwrap();
+        assert_eq!(data.len(), 1);
+        let cells = cells(data[0]);
+        assert!(columns.spans(&cells));
+        let name = columns.get(&cells, TestColumn::Name).unwrap();
+        assert_eq!(name.text().collect::<String>(), "Muster");
+    }
+
+    #[test]
+    fn test_data_cells() {
+        let document = document("<table><tr><th>Datum</th><th>Name</th></tr>\
+            <tr><td colspan=2>KW 23</td></tr>\
+            <tr><td></td></tr>\
+            <tr><td>2023-06-04</td><td>Muster</td></tr>\
+            <tr><td>2023-06-05</td></tr></table>");
+        let rows = document.select(&Selector::parse("tr").unwrap()).collect::<Vec<_>>();
+        let (columns, data) = ColumnMap::<TestColumn>::find(PageKind::Calendar, rows).unwrap();
+        assert!(columns.data_cells(PageKind::Calendar, data[0]).unwrap().is_none());
+        assert!(columns.data_cells(PageKind::Calendar, data[1]).unwrap().is_none());
+        assert_eq!(columns.data_cells(PageKind::Calendar, data[2]).unwrap().unwrap().len(), 2);
+        let error = columns.data_cells(PageKind::Calendar, data[3]).unwrap_err();
+        assert_eq!(error.message, "unexpected layout, row has 1 of 2 cells");
+    }
+
+    #[test]
+    fn test_missing_columns() {
+        let document = document("<table><tr><th>Datum</th><th>Zeit</th></tr></table>");
+        let rows = document.select(&Selector::parse("tr").unwrap()).collect::<Vec<_>>();
+        let error = ColumnMap::<TestColumn>::find(PageKind::Calendar, rows).unwrap_err();
+        assert_eq!(error.to_string(), "failed to parse calendar page: unexpected layout, missing columns: name");
+    }
+}
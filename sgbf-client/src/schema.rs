@@ -0,0 +1,223 @@
+//! JSON Schema and TypeScript definitions of the types the API serializes.
+//!
+//! Both are generated from the same [`schemars`] schemas, which follow the serde attributes of
+//! each type. [`Definitions::model`] holds everything in [`crate::model`], applications add
+//! their own types with [`Definitions::add`].
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use schemars::r#gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::model::{Address, Addresses, Day, DayOverview, Duty, EditAction, EntryType, Member, ParticipantType, Period, PersonEntry, Reservation, ReservationRequest, RosterEntry, RosterEntryType, Stats, TimeFrame};
+use crate::model::aircraft::{Aircraft, AircraftClass, FleetAircraft};
+
+const DEFINITIONS_PATH: &str = "#/definitions/";
+
+#[derive(Debug, Clone)]
+pub struct Definitions {
+    generator: SchemaGenerator,
+}
+
+impl Default for Definitions {
+    fn default() -> Self {
+        let settings = SchemaSettings::draft07().with(|settings| {
+            settings.definitions_path = DEFINITIONS_PATH.to_string();
+        });
+        Self { generator: settings.into_generator() }
+    }
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    #[serde(rename = "$schema")]
+    schema: &'a str,
+    definitions: BTreeMap<&'a String, &'a Schema>,
+}
+
+impl Definitions {
+    /// Every serialized type of [`crate::model`].
+    pub fn model() -> Self {
+        let mut definitions = Self::default();
+        definitions
+            .add::<Day>()
+            .add::<DayOverview>()
+            .add::<Duty>()
+            .add::<EditAction>()
+            .add::<EntryType>()
+            .add::<Member>()
+            .add::<Address>()
+            .add::<Addresses>()
+            .add::<ParticipantType>()
+            .add::<Period>()
+            .add::<PersonEntry>()
+            .add::<Reservation>()
+            .add::<ReservationRequest>()
+            .add::<RosterEntry>()
+            .add::<RosterEntryType>()
+            .add::<Stats>()
+            .add::<TimeFrame>()
+            .add::<Aircraft>()
+            .add::<AircraftClass>()
+            .add::<FleetAircraft>();
+        definitions
+    }
+
+    /// Adds `T` and every type it refers to.
+    pub fn add<T: JsonSchema>(&mut self) -> &mut Self {
+        self.generator.subschema_for::<T>();
+        self
+    }
+
+    fn sorted(&self) -> BTreeMap<&String, &Schema> {
+        self.generator.definitions().iter().collect()
+    }
+
+    /// A draft 7 document holding all types under `definitions`.
+    pub fn json_schema(&self) -> String {
+        let document = Document {
+            schema: "http://json-schema.org/draft-07/schema#",
+            definitions: self.sorted(),
+        };
+        let mut json = serde_json::to_string_pretty(&document).expect("schemas serialize to json");
+        json.push('\n');
+        json
+    }
+
+    /// An exported interface for every struct, and a type alias for everything else.
+    pub fn typescript(&self) -> String {
+        let mut typescript = String::new();
+        for (name, schema) in self.sorted() {
+            typescript.push_str(&comment(schema, ""));
+            let object = match schema {
+                Schema::Object(schema) if schema.subschemas.is_none() => schema.object.as_ref(),
+                _ => None,
+            };
+            match object {
+                Some(object) if !object.properties.is_empty() => {
+                    writeln!(typescript, "export interface {} {{", name).unwrap();
+                    for (property, schema) in &object.properties {
+                        let optional = if object.required.contains(property) { "" } else { "?" };
+                        typescript.push_str(&comment(schema, "    "));
+                        writeln!(typescript, "    {}{}: {};", property, optional, ts_type(schema)).unwrap();
+                    }
+                    typescript.push_str("}\n\n");
+                }
+                _ => writeln!(typescript, "export type {} = {};\n", name, ts_type(schema)).unwrap(),
+            }
+        }
+        typescript.truncate(typescript.trim_end().len());
+        typescript.push('\n');
+        typescript
+    }
+}
+
+fn comment(schema: &Schema, indent: &str) -> String {
+    let description = match schema {
+        Schema::Object(SchemaObject { metadata: Some(metadata), .. }) => metadata.description.as_ref(),
+        _ => None,
+    };
+    let Some(description) = description else {
+        return String::new();
+    };
+    // rustdoc links don't resolve in typescript
+    let description = description.replace("[`", "`").replace("`]", "`");
+    match description.lines().count() {
+        1 => format!("{}/** {} */\n", indent, description),
+        _ => {
+            let lines = description.lines()
+                .map(|line| format!("{} * {}", indent, line).trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!("{indent}/**\n{}\n{indent} */\n", lines)
+        }
+    }
+}
+
+fn ts_type(schema: &Schema) -> String {
+    let schema = match schema {
+        Schema::Bool(true) => return "unknown".to_string(),
+        Schema::Bool(false) => return "never".to_string(),
+        Schema::Object(schema) => schema,
+    };
+    if let Some(reference) = &schema.reference {
+        return reference.trim_start_matches(DEFINITIONS_PATH).to_string();
+    }
+    if let Some(values) = &schema.enum_values {
+        return values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(" | ");
+    }
+    if let Some(value) = &schema.const_value {
+        return value.to_string();
+    }
+    if let Some(subschemas) = &schema.subschemas {
+        let join = |schemas: &Vec<Schema>, separator| schemas.iter().map(ts_type).collect::<Vec<_>>().join(separator);
+        if let Some(schemas) = &subschemas.one_of {
+            return join(schemas, " | ");
+        }
+        if let Some(schemas) = &subschemas.any_of {
+            return join(schemas, " | ");
+        }
+        if let Some(schemas) = &subschemas.all_of {
+            return join(schemas, " & ");
+        }
+    }
+    let types = match &schema.instance_type {
+        Some(SingleOrVec::Single(instance_type)) => vec![**instance_type],
+        Some(SingleOrVec::Vec(instance_types)) => instance_types.clone(),
+        None => return "unknown".to_string(),
+    };
+    types.into_iter()
+        .map(|instance_type| match instance_type {
+            InstanceType::Null => "null".to_string(),
+            InstanceType::Boolean => "boolean".to_string(),
+            InstanceType::Integer | InstanceType::Number => "number".to_string(),
+            InstanceType::String => "string".to_string(),
+            InstanceType::Array => {
+                let items = match schema.array.as_ref().and_then(|array| array.items.as_ref()) {
+                    Some(SingleOrVec::Single(items)) => ts_type(items),
+                    _ => "unknown".to_string(),
+                };
+                match items.contains(' ') {
+                    true => format!("({})[]", items),
+                    false => format!("{}[]", items),
+                }
+            }
+            InstanceType::Object => match &schema.object {
+                Some(object) if !object.properties.is_empty() => {
+                    let properties = object.properties.iter()
+                        .map(|(property, schema)| {
+                            let optional = if object.required.contains(property) { "" } else { "?" };
+                            format!("{}{}: {}", property, optional, ts_type(schema))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("; ");
+                    format!("{{ {} }}", properties)
+                }
+                Some(object) => match &object.additional_properties {
+                    Some(values) => format!("Record<string, {}>", ts_type(values)),
+                    None => "Record<string, unknown>".to_string(),
+                },
+                None => "Record<string, unknown>".to_string(),
+            },
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_typescript() {
+        let typescript = Definitions::model().typescript();
+        assert!(typescript.contains("export type EditAction = \"edit\" | \"add\";\n"));
+        assert!(typescript.contains("export interface Reservation {\n    id: number;\n    period: Period;\n"));
+        // options may be missing or null
+        assert!(typescript.contains("    remarks?: string | null;\n"));
+        assert!(typescript.contains("    reservations?: Reservation[] | null;\n"));
+        // flattened into the fleet aircraft
+        assert!(typescript.contains("    reservable: boolean;\n    registrationNumber: string;\n"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Where roster data comes from.
+//!
+//! [`RosterSource`] covers what the API reads and writes upstream, so it can run against the live
+//! site through [`Client`] or [`HttpSource`], against captured pages with [`FixtureSource`], or
+//! against data set up in a test with [`FakeSource`].
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use chrono::NaiveDate;
+use futures_util::future::BoxFuture;
+use tokio::sync::OnceCell;
+use crate::client::{ClientBuilder, ClientError, DayBatch, Result};
+use crate::model::{time, Day, DayOverview, Duty, EditAction, Member, ParticipantType, Reservation, ReservationRequest};
+use crate::model::aircraft::FleetAircraft;
+use crate::model::query::{CalendarQuery, ReservationQuery};
+use crate::parsing::Parser;
+use crate::Client;
+
+pub trait RosterSource: fmt::Debug + Send + Sync {
+    fn get_calendar<'a>(&'a self, query: &'a CalendarQuery) -> BoxFuture<'a, Result<Vec<DayOverview>>>;
+
+    fn get_day(&self, date: NaiveDate) -> BoxFuture<'_, Result<Day>>;
+
+    /// Fetches several days, one after the other unless the source can do better.
+    fn get_days(&self, dates: Vec<NaiveDate>) -> BoxFuture<'_, DayBatch> {
+        Box::pin(async move {
+            let mut batch = DayBatch::default();
+            for date in dates {
+                match self.get_day(date).await {
+                    Ok(day) => {
+                        batch.days.insert(date, day);
+                    }
+                    Err(error) => batch.failures.push((date, error)),
+                }
+            }
+            batch
+        })
+    }
+
+    /// Updates the sign-up for the given day and returns the day as it is afterwards.
+    fn update_day(&self, date: NaiveDate, day: Day) -> BoxFuture<'_, Result<Day>>;
+
+    fn get_members(&self) -> BoxFuture<'_, Result<Vec<Member>>>;
+
+    fn get_reservations<'a>(&'a self, query: &'a ReservationQuery) -> BoxFuture<'a, Result<Vec<Reservation>>>;
+
+    fn get_fleet(&self) -> BoxFuture<'_, Result<Vec<FleetAircraft>>>;
+
+    /// Reservations are created, updated and cancelled in the name of the logged in user.
+    fn create_reservation<'a>(&'a self, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>>;
+
+    fn update_reservation<'a>(&'a self, id: i32, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>>;
+
+    fn cancel_reservation(&self, id: i32) -> BoxFuture<'_, Result<()>>;
+
+    /// The duty slot of the given type, `EditAction::Add` if nobody holds it yet.
+    fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<Duty>>;
+
+    fn update_duty(&self, date: NaiveDate, duty: Duty) -> BoxFuture<'_, Result<Duty>>;
+
+    fn remove_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<()>>;
+}
+
+impl RosterSource for Client {
+    fn get_calendar<'a>(&'a self, query: &'a CalendarQuery) -> BoxFuture<'a, Result<Vec<DayOverview>>> {
+        Box::pin(Client::get_calendar(self, query))
+    }
+
+    fn get_day(&self, date: NaiveDate) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(Client::get_day(self, date))
+    }
+
+    fn get_days(&self, dates: Vec<NaiveDate>) -> BoxFuture<'_, DayBatch> {
+        Box::pin(Client::get_days(self, dates))
+    }
+
+    fn update_day(&self, date: NaiveDate, day: Day) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(Client::update_day(self, date, day))
+    }
+
+    fn get_members(&self) -> BoxFuture<'_, Result<Vec<Member>>> {
+        Box::pin(Client::get_members(self))
+    }
+
+    fn get_reservations<'a>(&'a self, query: &'a ReservationQuery) -> BoxFuture<'a, Result<Vec<Reservation>>> {
+        Box::pin(Client::get_reservations(self, query))
+    }
+
+    fn get_fleet(&self) -> BoxFuture<'_, Result<Vec<FleetAircraft>>> {
+        Box::pin(Client::get_fleet(self))
+    }
+
+    fn create_reservation<'a>(&'a self, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(Client::create_reservation(self, reservation))
+    }
+
+    fn update_reservation<'a>(&'a self, id: i32, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(Client::update_reservation(self, id, reservation))
+    }
+
+    fn cancel_reservation(&self, id: i32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(Client::cancel_reservation(self, id))
+    }
+
+    fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(Client::get_duty(self, date, participant_type))
+    }
+
+    fn update_duty(&self, date: NaiveDate, duty: Duty) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(Client::update_duty(self, date, duty))
+    }
+
+    fn remove_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<()>> {
+        Box::pin(Client::remove_duty(self, date, participant_type))
+    }
+}
+
+/// The live site with a service account. Logs in on first use and again whenever the session
+/// expires, so it can be created before upstream is reachable.
+pub struct HttpSource {
+    builder: ClientBuilder,
+    username: String,
+    password: String,
+    client: OnceCell<Client>,
+}
+
+impl fmt::Debug for HttpSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpSource")
+            .field("username", &self.username)
+            .field("client", &self.client.get())
+            .finish()
+    }
+}
+
+impl HttpSource {
+    pub fn new(builder: ClientBuilder, username: &str, password: &str) -> Self {
+        Self {
+            builder,
+            username: username.to_string(),
+            password: password.to_string(),
+            client: OnceCell::new(),
+        }
+    }
+
+    pub async fn client(&self) -> Result<&Client> {
+        self.client.get_or_try_init(|| {
+            self.builder.clone()
+                .relogin(true)
+                .login(&self.username, &self.password)
+        }).await
+    }
+}
+
+impl RosterSource for HttpSource {
+    fn get_calendar<'a>(&'a self, query: &'a CalendarQuery) -> BoxFuture<'a, Result<Vec<DayOverview>>> {
+        Box::pin(async move { self.client().await?.get_calendar(query).await })
+    }
+
+    fn get_day(&self, date: NaiveDate) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move { self.client().await?.get_day(date).await })
+    }
+
+    fn get_days(&self, dates: Vec<NaiveDate>) -> BoxFuture<'_, DayBatch> {
+        Box::pin(async move {
+            match self.client().await {
+                Ok(client) => client.get_days(dates).await,
+                // report the login failure once, the days are all missing for the same reason
+                Err(error) => DayBatch {
+                    failures: dates.first().map(|date| (*date, error)).into_iter().collect(),
+                    ..Default::default()
+                },
+            }
+        })
+    }
+
+    fn update_day(&self, date: NaiveDate, day: Day) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move { self.client().await?.update_day(date, day).await })
+    }
+
+    fn get_members(&self) -> BoxFuture<'_, Result<Vec<Member>>> {
+        Box::pin(async move { self.client().await?.get_members().await })
+    }
+
+    fn get_reservations<'a>(&'a self, query: &'a ReservationQuery) -> BoxFuture<'a, Result<Vec<Reservation>>> {
+        Box::pin(async move { self.client().await?.get_reservations(query).await })
+    }
+
+    fn get_fleet(&self) -> BoxFuture<'_, Result<Vec<FleetAircraft>>> {
+        Box::pin(async move { self.client().await?.get_fleet().await })
+    }
+
+    fn create_reservation<'a>(&'a self, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move { self.client().await?.create_reservation(reservation).await })
+    }
+
+    fn update_reservation<'a>(&'a self, id: i32, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move { self.client().await?.update_reservation(id, reservation).await })
+    }
+
+    fn cancel_reservation(&self, id: i32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.client().await?.cancel_reservation(id).await })
+    }
+
+    fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move { self.client().await?.get_duty(date, participant_type).await })
+    }
+
+    fn update_duty(&self, date: NaiveDate, duty: Duty) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move { self.client().await?.update_duty(date, duty).await })
+    }
+
+    fn remove_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { self.client().await?.remove_duty(date, participant_type).await })
+    }
+}
+
+/// Captured pages in a directory, e.g. fixtures run through [`crate::anonymize`]: `calendar.html`,
+/// `reservations.html`, `members.html`, `day-<YYYY-MM-DD>.html` and
+/// `duty-<YYYY-MM-DD>-<fe_t>.html`. Queries are ignored and the pages can't be written to.
+#[derive(Debug, Clone)]
+pub struct FixtureSource {
+    dir: PathBuf,
+}
+
+impl FixtureSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The raw page, `ClientError::Unrecorded` if the directory doesn't hold it.
+    pub fn page(&self, name: &str) -> Result<String> {
+        let path = self.dir.join(name);
+        std::fs::read_to_string(&path)
+            .map_err(|_| ClientError::Unrecorded(path.display().to_string()))
+    }
+}
+
+impl RosterSource for FixtureSource {
+    fn get_calendar<'a>(&'a self, _query: &'a CalendarQuery) -> BoxFuture<'a, Result<Vec<DayOverview>>> {
+        Box::pin(async move { Ok(Parser::default().parse_calendar(self.page("calendar.html")?)?) })
+    }
+
+    fn get_day(&self, date: NaiveDate) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move {
+            let page = self.page(&format!("day-{}.html", date.format("%Y-%m-%d")))?;
+            Ok(Parser::default().parse_day(page)?)
+        })
+    }
+
+    fn update_day(&self, date: NaiveDate, _day: Day) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't update {}", date))) })
+    }
+
+    fn get_members(&self) -> BoxFuture<'_, Result<Vec<Member>>> {
+        Box::pin(async move { Ok(Parser::default().parse_members(self.page("members.html")?)?) })
+    }
+
+    fn get_reservations<'a>(&'a self, _query: &'a ReservationQuery) -> BoxFuture<'a, Result<Vec<Reservation>>> {
+        Box::pin(async move { Ok(Parser::default().parse_reservations(self.page("reservations.html")?)?) })
+    }
+
+    fn get_fleet(&self) -> BoxFuture<'_, Result<Vec<FleetAircraft>>> {
+        Box::pin(async move { Ok(Parser::default().parse_fleet(self.page("reservations.html")?)?) })
+    }
+
+    fn create_reservation<'a>(&'a self, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't reserve {}", reservation.plane))) })
+    }
+
+    fn update_reservation<'a>(&'a self, id: i32, _reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't update reservation {}", id))) })
+    }
+
+    fn cancel_reservation(&self, id: i32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't cancel reservation {}", id))) })
+    }
+
+    fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move {
+            let page = self.page(&format!("duty-{}-{}.html", date.format("%Y-%m-%d"), participant_type.code()))?;
+            Ok(Parser::default().parse_duty(page)?)
+        })
+    }
+
+    fn update_duty(&self, date: NaiveDate, _duty: Duty) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't update the duty on {}", date))) })
+    }
+
+    fn remove_duty(&self, date: NaiveDate, _participant_type: ParticipantType) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move { Err(ClientError::WriteRejected(format!("fixtures are read-only, can't remove the duty on {}", date))) })
+    }
+}
+
+/// The data behind a [`FakeSource`].
+#[derive(Debug, Clone, Default)]
+pub struct FakeRoster {
+    pub calendar: Vec<DayOverview>,
+    pub days: BTreeMap<NaiveDate, Day>,
+    pub members: Vec<Member>,
+    pub reservations: Vec<Reservation>,
+    pub fleet: Vec<FleetAircraft>,
+    pub duties: BTreeMap<(NaiveDate, ParticipantType), Duty>,
+    /// Who new reservations are made by.
+    pub user: String,
+}
+
+/// In-memory roster for tests. Days that were never set up read as empty sign-up forms, updates
+/// are stored as they are sent.
+#[derive(Debug, Default)]
+pub struct FakeSource {
+    roster: Mutex<FakeRoster>,
+}
+
+impl FakeSource {
+    pub fn new(roster: FakeRoster) -> Self {
+        Self { roster: Mutex::new(roster) }
+    }
+
+    /// Changes the data, e.g. to simulate someone else signing up between two cache updates.
+    pub fn update(&self, change: impl FnOnce(&mut FakeRoster)) {
+        change(&mut self.roster.lock().unwrap());
+    }
+
+    pub fn snapshot(&self) -> FakeRoster {
+        self.roster.lock().unwrap().clone()
+    }
+}
+
+fn empty_day() -> Day {
+    Day {
+        entries: vec![],
+        action: EditAction::Add,
+        id: None,
+        participant_type: ParticipantType::GliderPilot,
+        format: "text".to_string(),
+        remarks: None,
+        entry_type: None,
+        reservations: None,
+    }
+}
+
+fn empty_duty(participant_type: ParticipantType) -> Duty {
+    Duty {
+        action: EditAction::Add,
+        id: None,
+        participant_type,
+        format: "text".to_string(),
+        time_frame: None,
+        remarks: None,
+    }
+}
+
+impl RosterSource for FakeSource {
+    fn get_calendar<'a>(&'a self, _query: &'a CalendarQuery) -> BoxFuture<'a, Result<Vec<DayOverview>>> {
+        Box::pin(async move { Ok(self.roster.lock().unwrap().calendar.clone()) })
+    }
+
+    fn get_day(&self, date: NaiveDate) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move { Ok(self.roster.lock().unwrap().days.get(&date).cloned().unwrap_or_else(empty_day)) })
+    }
+
+    fn update_day(&self, date: NaiveDate, day: Day) -> BoxFuture<'_, Result<Day>> {
+        Box::pin(async move {
+            let mut roster = self.roster.lock().unwrap();
+            let next_id = roster.days.values().filter_map(|day| day.id).max().unwrap_or_default() + 1;
+            let day = Day {
+                action: EditAction::Edit,
+                id: day.id.or(Some(next_id)),
+                ..day
+            };
+            roster.days.insert(date, day.clone());
+            Ok(day)
+        })
+    }
+
+    fn get_members(&self) -> BoxFuture<'_, Result<Vec<Member>>> {
+        Box::pin(async move { Ok(self.roster.lock().unwrap().members.clone()) })
+    }
+
+    fn get_reservations<'a>(&'a self, _query: &'a ReservationQuery) -> BoxFuture<'a, Result<Vec<Reservation>>> {
+        Box::pin(async move { Ok(self.roster.lock().unwrap().reservations.clone()) })
+    }
+
+    fn get_fleet(&self) -> BoxFuture<'_, Result<Vec<FleetAircraft>>> {
+        Box::pin(async move { Ok(self.roster.lock().unwrap().fleet.clone()) })
+    }
+
+    fn create_reservation<'a>(&'a self, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move {
+            let mut roster = self.roster.lock().unwrap();
+            let id = roster.reservations.iter().map(|reservation| reservation.id).max().unwrap_or_default() + 1;
+            let reservation = Reservation {
+                id,
+                period: reservation.period.clone(),
+                plane: reservation.plane.clone(),
+                reserved_by: roster.user.clone(),
+                created_at: time::start_of_day(chrono::Utc::now().date_naive()),
+                comments: reservation.comment.iter().cloned().collect(),
+            };
+            roster.reservations.push(reservation.clone());
+            Ok(reservation)
+        })
+    }
+
+    fn update_reservation<'a>(&'a self, id: i32, reservation: &'a ReservationRequest) -> BoxFuture<'a, Result<Reservation>> {
+        Box::pin(async move {
+            let mut roster = self.roster.lock().unwrap();
+            let existing = roster.reservations.iter_mut()
+                .find(|existing| existing.id == id)
+                .ok_or_else(|| ClientError::WriteRejected(format!("no reservation {}", id)))?;
+            existing.period = reservation.period.clone();
+            existing.plane = reservation.plane.clone();
+            existing.comments = reservation.comment.iter().cloned().collect();
+            Ok(existing.clone())
+        })
+    }
+
+    fn cancel_reservation(&self, id: i32) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.roster.lock().unwrap().reservations.retain(|reservation| reservation.id != id);
+            Ok(())
+        })
+    }
+
+    fn get_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move {
+            let roster = self.roster.lock().unwrap();
+            Ok(roster.duties.get(&(date, participant_type)).cloned().unwrap_or_else(|| empty_duty(participant_type)))
+        })
+    }
+
+    fn update_duty(&self, date: NaiveDate, duty: Duty) -> BoxFuture<'_, Result<Duty>> {
+        Box::pin(async move {
+            if duty.time_frame.is_none() {
+                return Err(ClientError::InvalidRequest("a duty needs a time frame".to_string()));
+            }
+            let mut roster = self.roster.lock().unwrap();
+            let next_id = roster.duties.values().filter_map(|duty| duty.id).max().unwrap_or_default() + 1;
+            let duty = Duty {
+                action: EditAction::Edit,
+                id: duty.id.or(Some(next_id)),
+                ..duty
+            };
+            roster.duties.insert((date, duty.participant_type), duty.clone());
+            Ok(duty)
+        })
+    }
+
+    fn remove_duty(&self, date: NaiveDate, participant_type: ParticipantType) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.roster.lock().unwrap().duties.remove(&(date, participant_type));
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::model::RosterEntryType;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fake_source() {
+        let source: Box<dyn RosterSource> = Box::new(FakeSource::default());
+        let date = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap();
+        let day = source.get_day(date).await.unwrap();
+        assert_eq!(day.action, EditAction::Add);
+        let updated = source.update_day(date, Day { entry_type: Some(RosterEntryType::Definite), ..day }).await.unwrap();
+        assert_eq!(updated.action, EditAction::Edit);
+        let batch = source.get_days(vec![date, date.succ_opt().unwrap()]).await;
+        assert_eq!(batch.days[&date].entry_type, Some(RosterEntryType::Definite));
+        assert_eq!(batch.days.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fixture_source_is_read_only() {
+        let source = FixtureSource::new(std::env::temp_dir().join("sgbf-no-fixtures"));
+        assert!(matches!(source.get_members().await, Err(ClientError::Unrecorded(_))));
+        let date = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap();
+        assert!(matches!(source.update_day(date, empty_day()).await, Err(ClientError::WriteRejected(_))));
+        assert!(matches!(source.cancel_reservation(7).await, Err(ClientError::WriteRejected(_))));
+        let duty = empty_duty(ParticipantType::TowingPilot);
+        assert!(matches!(source.update_duty(date, duty).await, Err(ClientError::WriteRejected(_))));
+    }
+
+    #[tokio::test]
+    async fn test_fake_duty() {
+        let source = FakeSource::default();
+        let date = NaiveDate::from_ymd_opt(2023, 6, 4).unwrap();
+        let duty = source.get_duty(date, ParticipantType::TowingPilot).await.unwrap();
+        assert_eq!(duty.action, EditAction::Add);
+        assert!(matches!(source.update_duty(date, duty.clone()).await, Err(ClientError::InvalidRequest(_))));
+        let duty = Duty { time_frame: Some("09:00 - 17:00".parse().unwrap()), ..duty };
+        source.update_duty(date, duty).await.unwrap();
+        assert_eq!(source.get_duty(date, ParticipantType::TowingPilot).await.unwrap().action, EditAction::Edit);
+        assert_eq!(source.get_duty(date, ParticipantType::WinchOperator).await.unwrap().action, EditAction::Add);
+        source.remove_duty(date, ParticipantType::TowingPilot).await.unwrap();
+        assert!(source.snapshot().duties.is_empty());
+    }
+}
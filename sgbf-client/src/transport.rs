@@ -0,0 +1,275 @@
+//! How [`Client`](crate::Client) talks to upstream: over the network, over the network while
+//! recording every exchange to a directory, or by replaying a recorded directory offline.
+//!
+//! Each exchange is stored as `<key>-<n>.json` with the request and response metadata and
+//! `<key>-<n>.html` with the body, where `key` is derived from the method, path, query and form
+//! body of the request. Credentials, the session cookie and `Cookie` headers are never written.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use reqwest::header::{HeaderValue, LOCATION, SET_COOKIE};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+use crate::client::{ClientError, SESSION_COOKIE};
+use crate::hash::fnv1a;
+
+const REDACTED: &str = "REDACTED";
+// form fields and query parameters whose values are never recorded
+const SECRET_FIELDS: &[&str] = &["login_user", "login_password", SESSION_COOKIE];
+
+#[derive(Debug, Clone, Default)]
+pub enum Transport {
+    #[default]
+    Network,
+    Record(Recorder),
+    Replay(Replayer),
+}
+
+impl Transport {
+    pub fn record(dir: impl Into<PathBuf>) -> Self {
+        Transport::Record(Recorder::new(dir))
+    }
+
+    pub fn replay(dir: impl Into<PathBuf>) -> Self {
+        Transport::Replay(Replayer::new(dir))
+    }
+}
+
+/// The parts of an upstream request that identify it in a recording.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path and query, without the host so recordings work against any base url.
+    pub path: String,
+    pub body: Option<String>,
+}
+
+impl RecordedRequest {
+    pub(crate) fn new(request: &reqwest::Request) -> Self {
+        let url = request.url();
+        let mut path = url.path().to_string();
+        if url.query().is_some() {
+            let query = redact_pairs(url.query_pairs());
+            path = format!("{}?{}", path, query);
+        }
+        let body = request.body()
+            .and_then(|body| body.as_bytes())
+            .map(|body| redact_pairs(url::form_urlencoded::parse(body)));
+        Self {
+            method: request.method().to_string(),
+            path,
+            body,
+        }
+    }
+
+    /// File name stem shared by every recording of this request.
+    fn key(&self) -> String {
+        let slug = self.path.split('?').next().unwrap_or_default()
+            .trim_matches('/')
+            .replace(['/', '.'], "_");
+        let hash = fnv1a(format!("{} {} {}", self.method, self.path, self.body.as_deref().unwrap_or_default()).as_bytes());
+        format!("{}-{}-{:08x}", self.method.to_lowercase(), slug, hash as u32)
+    }
+}
+
+/// An upstream response, read completely.
+#[derive(Debug, Clone)]
+pub(crate) struct RawResponse {
+    pub status: StatusCode,
+    pub url: Url,
+    pub location: Option<String>,
+    pub set_cookies: Vec<HeaderValue>,
+    pub body: String,
+}
+
+impl RawResponse {
+    pub(crate) async fn read(response: reqwest::Response) -> reqwest::Result<Self> {
+        let status = response.status();
+        let url = response.url().clone();
+        let location = response.headers().get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .map(|location| location.to_string());
+        let set_cookies = response.headers().get_all(SET_COOKIE).iter().cloned().collect();
+        let body = response.text().await?;
+        Ok(Self { status, url, location, set_cookies, body })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    request: RecordedRequest,
+    status: u16,
+    location: Option<String>,
+    set_cookies: Vec<String>,
+}
+
+/// Writes every exchange to a directory.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub(crate) fn record(&self, request: &RecordedRequest, response: &RawResponse) {
+        if let Err(error) = self.write(request, response) {
+            warn!(%error, dir = %self.dir.display(), "failed to record upstream response");
+        }
+    }
+
+    fn write(&self, request: &RecordedRequest, response: &RawResponse) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let recording = Recording {
+            request: request.clone(),
+            status: response.status.as_u16(),
+            location: response.location.clone(),
+            set_cookies: response.set_cookies.iter()
+                .filter_map(|cookie| cookie.to_str().ok())
+                .map(redact_cookie)
+                .collect(),
+        };
+        let key = request.key();
+        // the first free index keeps repeated requests in order, even across clients
+        for n in 0.. {
+            let path = self.dir.join(format!("{}-{:03}.json", key, n));
+            let file = match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            };
+            serde_json::to_writer_pretty(file, &recording)?;
+            fs::write(path.with_extension("html"), &response.body)?;
+            debug!(path = %path.display(), "recorded upstream response");
+            break;
+        }
+        Ok(())
+    }
+}
+
+/// Serves recorded exchanges instead of hitting the network. Repeated requests get the recorded
+/// responses in order and the last one once the recording runs out.
+#[derive(Debug, Clone)]
+pub struct Replayer {
+    dir: PathBuf,
+    cursors: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl Replayer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            cursors: Default::default(),
+        }
+    }
+
+    pub(crate) fn replay(&self, request: &RecordedRequest, url: &Url) -> Result<RawResponse, ClientError> {
+        let key = request.key();
+        let path = |n: usize| self.dir.join(format!("{}-{:03}.json", key, n));
+        let path = {
+            let mut cursors = self.cursors.lock().unwrap();
+            let cursor = cursors.entry(key.clone()).or_default();
+            if path(*cursor).exists() {
+                *cursor += 1;
+            }
+            cursor.checked_sub(1)
+                .map(path)
+                .ok_or_else(|| ClientError::Unrecorded(format!("{} {}", request.method, request.path)))?
+        };
+        read_recording(&path, url)
+            .map_err(|e| ClientError::Unrecorded(format!("{}: {}", path.display(), e)))
+    }
+}
+
+fn read_recording(path: &Path, url: &Url) -> io::Result<RawResponse> {
+    let recording: Recording = serde_json::from_slice(&fs::read(path)?)?;
+    let body = fs::read_to_string(path.with_extension("html"))?;
+    Ok(RawResponse {
+        status: StatusCode::from_u16(recording.status).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        url: url.clone(),
+        location: recording.location,
+        set_cookies: recording.set_cookies.iter()
+            .filter_map(|cookie| HeaderValue::from_str(cookie).ok())
+            .collect(),
+        body,
+    })
+}
+
+fn redact_pairs<'a>(pairs: impl Iterator<Item = (std::borrow::Cow<'a, str>, std::borrow::Cow<'a, str>)>) -> String {
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (name, value) in pairs {
+        let value = if SECRET_FIELDS.contains(&name.as_ref()) { REDACTED } else { value.as_ref() };
+        serializer.append_pair(&name, value);
+    }
+    serializer.finish()
+}
+
+// keeps the cookie name and attributes so replayed clients still hold a session
+fn redact_cookie(cookie: &str) -> String {
+    match cookie.split_once(';') {
+        Some((pair, attributes)) => format!("{};{}", redact_cookie(pair), attributes),
+        None => match cookie.split_once('=') {
+            Some((name, _)) => format!("{}={}", name.trim(), REDACTED),
+            None => cookie.to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sgbf-transport-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_request_redacts_credentials() {
+        let request = reqwest::Client::new().post("https://example.com/roster/index.php?PHPSESSID=abc")
+            .form(&[("login_user", "pilot"), ("login_password", "secret"), ("save", "Login")])
+            .build()
+            .unwrap();
+        let recorded = RecordedRequest::new(&request);
+        assert_eq!(recorded.path, "/roster/index.php?PHPSESSID=REDACTED");
+        assert_eq!(recorded.body.as_deref(), Some("login_user=REDACTED&login_password=REDACTED&save=Login"));
+        assert_eq!(redact_cookie("PHPSESSID=abc; path=/"), "PHPSESSID=REDACTED; path=/");
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = temp_dir("replay");
+        let url = Url::parse("https://example.com/roster/day.php").unwrap();
+        let request = reqwest::Client::new().get(url.clone()).build().unwrap();
+        let request = RecordedRequest::new(&request);
+        let recorder = Recorder::new(&dir);
+        for body in ["first", "second"] {
+            recorder.record(&request, &RawResponse {
+                status: StatusCode::OK,
+                url: url.clone(),
+                location: None,
+                set_cookies: vec![HeaderValue::from_static("PHPSESSID=abc; path=/")],
+                body: body.to_string(),
+            });
+        }
+
+        let replayer = Replayer::new(&dir);
+        let bodies = (0..3)
+            .map(|_| replayer.replay(&request, &url).unwrap().body)
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, ["first", "second", "second"]);
+        let response = replayer.replay(&request, &url).unwrap();
+        assert_eq!(response.set_cookies, [HeaderValue::from_static("PHPSESSID=REDACTED; path=/")]);
+
+        let other = RecordedRequest { method: "POST".to_string(), ..request };
+        assert!(matches!(replayer.replay(&other, &url), Err(ClientError::Unrecorded(_))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "blocking")]
+
+mod support;
+
+use std::time::Duration;
+use sgbf_client::client::blocking::Client;
+use sgbf_client::client::ClientBuilder;
+use sgbf_client::limiter::RetryPolicy;
+use support::{StandIn, MENU};
+
+#[test]
+fn test_sequential_clients() {
+    // upstream keeps running while the clients and their runtimes come and go
+    let server = tokio::runtime::Runtime::new().unwrap();
+    let upstream = server.block_on(StandIn::start(MENU));
+    let builder = ClientBuilder::new()
+        .base_url(upstream.url())
+        .timeout(Duration::from_secs(2))
+        .retry_policy(RetryPolicy { max_retries: 0, ..Default::default() });
+    let first = Client::with_token(builder.clone(), "first").unwrap();
+    let second = Client::with_token(builder.clone(), "second").unwrap();
+    // the first runtime is idle while the second client sends its request
+    assert_eq!(first.get_user().unwrap(), None);
+    assert_eq!(second.get_user().unwrap(), None);
+    assert_eq!(first.get_user().unwrap(), None);
+    drop(first);
+    let third = Client::with_token(builder, "third").unwrap();
+    assert_eq!(third.get_user().unwrap(), None);
+    assert_eq!(second.get_user().unwrap(), None);
+    // connections stay with the runtime that opened them
+    assert_eq!(upstream.connections(), 3);
+}
@@ -0,0 +1,21 @@
+mod support;
+
+use sgbf_client::client::ClientBuilder;
+use sgbf_client::limiter::{RateLimitConfig, RateLimiter};
+use support::{StandIn, MENU};
+
+#[tokio::test]
+async fn test_sessions_share_connections() {
+    let upstream = StandIn::start(MENU).await;
+    let builder = ClientBuilder::new()
+        .base_url(upstream.url())
+        .rate_limiter(RateLimiter::new(RateLimitConfig { requests_per_second: 0.0, max_concurrent: 1 }))
+        .pool()
+        .unwrap();
+    for token in ["first", "second", "third"] {
+        let client = builder.clone().with_token(token).unwrap();
+        assert_eq!(client.get_user().await.unwrap(), None);
+        assert_eq!(client.get_token().unwrap(), token);
+    }
+    assert_eq!(upstream.connections(), 1);
+}
@@ -0,0 +1,69 @@
+//! A local stand-in for upstream, so connection handling can be tested and benchmarked without
+//! hitting schlepppiloten.ch. It answers every request with the same page over keep-alive
+//! HTTP/1.1 and counts the connections it accepted.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+pub const MENU: &str = "<html><body><p>Anmelden</p></body></html>";
+
+pub struct StandIn {
+    url: String,
+    connections: Arc<AtomicUsize>,
+}
+
+impl StandIn {
+    pub async fn start(page: &'static str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(serve(stream, page));
+            }
+        });
+        Self { url, connections }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn connections(&self) -> usize {
+        self.connections.load(Ordering::SeqCst)
+    }
+}
+
+async fn serve(mut stream: TcpStream, page: &'static str) {
+    let response = format!("HTTP/1.1 200 OK\r\ncontent-type: text/html\r\ncontent-length: {}\r\n\r\n{}", page.len(), page);
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        // answer every complete request in the buffer, then wait for more
+        while let Some(end) = request_end(&buffer) {
+            buffer.drain(..end);
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+        }
+    }
+}
+
+// the length of the first request in the buffer, including its body
+fn request_end(buffer: &[u8]) -> Option<usize> {
+    let head = buffer.windows(4).position(|window| window == b"\r\n\r\n")? + 4;
+    let content_length = String::from_utf8_lossy(&buffer[..head]).lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    (buffer.len() >= head + content_length).then_some(head + content_length)
+}